@@ -1,28 +1,22 @@
 use std::{fs, path::Path};
-use qrbill::{iso11649::Iso11649, Address, Currency, Language, QRBill, QRBillOptions, Reference, StructuredAddress};
+use qrbill::{iso11649::Iso11649, Address, Currency, FontSize, Language, QRBill, QRBillOptions, Reference, StructuredAddress};
 
 fn main() -> anyhow::Result<()> {
     let qrbill = QRBill::new(QRBillOptions {
-        account: "CH5800791123000889012".parse()?,
-        creditor: Address::Structured(StructuredAddress {
-            name: "Noah Huesser".to_string(),
-            street: "Ammerswilerstrasse".to_string(),
-            house_number: "31F".to_string(),
-            postal_code: "5600".to_string(),
-            city: "Lenzburg".to_string(),
-            country: isocountry::CountryCode::CHE,
-        }),
-        amount: None, //Some(42.0),
-        currency: qrbill::Currency::SwissFranc,
         due_date: Some(chrono::NaiveDate::from_ymd_opt(2024, 6, 30).unwrap()),
-        //due_date: None,
-        debtor: None,
-        reference: Reference::None,
-        extra_infos: Some("This that and the other".into()), //None,
-        alternative_processes: vec![],
-        language: qrbill::Language::English,
-        top_line: true,
-        payment_line: true,
+        extra_infos: Some("This that and the other".into()),
+        separate_note: true,
+        ..QRBillOptions::new(
+            "CH5800791123000889012".parse()?,
+            Address::Structured(StructuredAddress {
+                name: "Noah Huesser".to_string(),
+                street: "Ammerswilerstrasse".to_string(),
+                house_number: "31F".to_string(),
+                postal_code: "5600".to_string(),
+                city: "Lenzburg".to_string(),
+                country: isocountry::CountryCode::CHE,
+            }),
+        )
     })?;
 
     let out_dir = "example-output".to_owned();
@@ -57,13 +51,20 @@ fn main() -> anyhow::Result<()> {
         })),
         reference: Reference::Scor(Iso11649::new("Abcd 1234 áü")),
         extra_infos: Some("Extra infos".into()),
+        bill_information: None,
         alternative_processes: vec![
             "Alternative process 1".into(),
             "Another alternative process".into()
         ],
         language: Language::French,
+        texts: None,
+        font_size: FontSize::new(9.0)?,
         top_line: true,
+        separate_note: true,
         payment_line: true,
+        epc_code: None,
+        due_date_heading: true,
+        svg_id: None,
     })?;
 
     let base = Path::new(&out_dir).join("test2");
@@ -1,11 +1,11 @@
-/// Examples taken from
-///
-/// https://www.six-group.com/dam/download/banking-services/standardization/qr-bill/style-guide-qr-bill-en.pdf
-///
-/// This document is included in this repository at `qr-standard-docs/style-guide-qr-bill-en.pdf`
+//! Examples taken from
+//!
+//! https://www.six-group.com/dam/download/banking-services/standardization/qr-bill/style-guide-qr-bill-en.pdf
+//!
+//! This document is included in this repository at `qr-standard-docs/style-guide-qr-bill-en.pdf`
 
 use std::{collections::HashMap, fs, path::Path};
-use qrbill::{esr, iso11649::Iso11649, Address, Language, QRBill, QRBillOptions, Reference, StructuredAddress};
+use qrbill::{esr, iso11649::Iso11649, Address, FontSize, Language, QRBill, QRBillOptions, Reference, StructuredAddress};
 
 
 fn main() -> anyhow::Result<()> {
@@ -122,10 +122,17 @@ fn make(
         debtor,
         reference: reference.clone(),
         extra_infos: extra_infos.map(Into::into),
+        bill_information: None,
         alternative_processes: vec![],
         language: Language::English,
+        texts: None,
+        font_size: FontSize::default(),
         top_line: true,
+        separate_note: true,
         payment_line: true,
+        epc_code: None,
+        due_date_heading: true,
+        svg_id: None,
     })?;
 
     Ok(qrbill)
@@ -0,0 +1,106 @@
+//! Regenerates `src/bank/directory.rs` from a local copy of the SIX bank
+//! master data CSV (Bank Master 3.0, semicolon or comma separated):
+//!
+//!     cargo run --example update-bank-directory -- bankmaster.csv
+//!
+//! Older exports with German column names (`BC-Nr`, `Bank/Institut`,
+//! `SWIFT`) are understood as well.
+
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::Path};
+
+use anyhow::{anyhow, bail, Context};
+
+const OUTPUT: &str = "src/bank/directory.rs";
+
+const IID_COLUMNS: [&str; 3] = ["iid", "bc-nr", "bcnr"];
+const NAME_COLUMNS: [&str; 2] = ["bankorinstitutionname", "bank/institut"];
+const BIC_COLUMNS: [&str; 2] = ["bic", "swift"];
+const ENTRY_TYPE_COLUMNS: [&str; 1] = ["entrytype"];
+
+/// IIDs reserved for QR-IBANs
+const QR_IIDS: std::ops::RangeInclusive<u32> = 30000..=31999;
+
+struct Entry {
+    name: String,
+    bic: Option<String>,
+    qr_iid: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = std::env::args().nth(1).ok_or_else(|| anyhow!("usage: update-bank-directory <bankmaster.csv>"))?;
+    let csv = fs::read(&input).with_context(|| format!("reading {input}"))?;
+    // The SIX exports are encoded in UTF-8, older ones in Latin-1
+    let csv = String::from_utf8(csv).unwrap_or_else(|e| e.into_bytes().iter().map(|&b| b as char).collect());
+
+    let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+    let header = lines.next().ok_or_else(|| anyhow!("{input} is empty"))?;
+    let separator = if header.contains(';') { ';' } else { ',' };
+    let header = split(header, separator);
+    let column = |names: &[&str]| {
+        header.iter().position(|h| names.contains(&h.trim_start_matches('\u{feff}').to_lowercase().as_str()))
+    };
+    let iid_column = column(&IID_COLUMNS).ok_or_else(|| anyhow!("no IID column in {input}"))?;
+    let name_column = column(&NAME_COLUMNS).ok_or_else(|| anyhow!("no bank name column in {input}"))?;
+    let bic_column = column(&BIC_COLUMNS);
+    let entry_type_column = column(&ENTRY_TYPE_COLUMNS);
+
+    let mut entries = BTreeMap::new();
+    for (number, line) in lines.enumerate() {
+        let fields = split(line, separator);
+        let field = |i: usize| fields.get(i).map(|f| f.trim()).filter(|f| !f.is_empty());
+        let Some(iid) = field(iid_column) else { continue };
+        let iid: u32 = iid.parse().with_context(|| format!("line {}: invalid IID '{iid}'", number + 2))?;
+        if iid > 99999 {
+            bail!("line {}: IID {iid} has more than 5 digits", number + 2);
+        }
+        let qr_iid = QR_IIDS.contains(&iid)
+            || entry_type_column.and_then(field).is_some_and(|t| t.eq_ignore_ascii_case("QR"));
+        // Branches share the IID of their bank; the first entry is kept
+        entries.entry(iid).or_insert(Entry {
+            name: field(name_column).unwrap_or_default().to_string(),
+            bic: bic_column.and_then(field).map(str::to_string),
+            qr_iid,
+        });
+    }
+
+    let mut out = String::new();
+    writeln!(out, "// Generated by `cargo run --example update-bank-directory` from")?;
+    writeln!(out, "// {}. Do not edit by hand.", Path::new(&input).file_name().unwrap_or_default().to_string_lossy())?;
+    writeln!(out)?;
+    writeln!(out, "use super::Bank;")?;
+    writeln!(out)?;
+    writeln!(out, "#[rustfmt::skip]")?;
+    writeln!(out, "pub(super) static BANKS: [Bank; {}] = [", entries.len())?;
+    for (iid, entry) in &entries {
+        writeln!(
+            out,
+            "    Bank {{ iid: {iid:>5}, bic: {:<17}, qr_iid: {:<5}, name: {:?} }},",
+            entry.bic.as_ref().map_or("None".to_string(), |bic| format!("Some({bic:?})")),
+            entry.qr_iid,
+            entry.name,
+        )?;
+    }
+    writeln!(out, "];")?;
+    fs::write(OUTPUT, out).with_context(|| format!("writing {OUTPUT}"))?;
+    println!("Wrote {} banks to {OUTPUT}", entries.len());
+    Ok(())
+}
+
+/// Split a CSV line, honouring double quoted fields
+fn split(line: &str, separator: char) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
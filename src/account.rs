@@ -0,0 +1,125 @@
+//! Offline conversion of older Swiss account numbers into IBANs, ready for
+//! `QRBillOptions::account`.
+
+use crate::{checkdigit, Iban};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("A postal account must look like 01-12345-6, but '{0}' was given.")]
+    InvalidPostalAccount(String),
+    #[error("The check digit of the postal account should be {expected}, but is {found}.")]
+    InvalidChecksum { expected: u8, found: u8 },
+    #[error("A clearing number must have between 3 and 5 digits, but '{0}' was given.")]
+    InvalidClearingNumber(String),
+    #[error("An account number may have at most 12 letters and digits, but '{0}' was given.")]
+    InvalidAccountNumber(String),
+    #[error("The resulting IBAN {0} is invalid.")]
+    InvalidIban(String),
+}
+
+/// Institution identification (IID) of PostFinance
+const POSTFINANCE_IID: &str = "09000";
+
+/// Convert a PostFinance postal account, such as `80-470-3`, into its IBAN
+/// `CH.. 0900 0000 8000 0470 3`.
+///
+/// The account consists of a 2 digit prefix, a number of up to 6 digits and a
+/// modulo 10 recursive check digit over the prefix and the zero-padded number.
+pub fn postal_account_to_iban(account: &str) -> Result<Iban, Error> {
+    let invalid = || Error::InvalidPostalAccount(account.to_string());
+    let parts: Vec<&str> = account.trim().split('-').collect();
+    let [prefix, number, check_digit] = parts[..] else { return Err(invalid()) };
+    let all_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if !(all_digits(prefix) && all_digits(number) && all_digits(check_digit))
+        || prefix.len() != 2
+        || number.len() > 6
+        || check_digit.len() != 1
+    {
+        return Err(invalid());
+    }
+
+    let digits = format!("{prefix}{number:0>6}");
+    let expected = checkdigit::mod10_recursive(&digits).ok_or_else(invalid)?;
+    let found = check_digit.as_bytes()[0] - b'0';
+    if expected != found {
+        return Err(Error::InvalidChecksum { expected, found });
+    }
+    swiss_iban(POSTFINANCE_IID, &format!("{digits}{check_digit}"))
+}
+
+/// Convert a bank clearing number and account number into an IBAN, by the
+/// generic Swiss rule: the clearing number, padded with leading zeros to 5
+/// digits, becomes the IID, and the account number, without separators and
+/// padded with leading zeros to 12 characters, the account part.
+///
+/// Many banks use their own rules instead, so the IBAN the bank itself states
+/// should be preferred where available.
+pub fn clearing_to_iban(clearing_number: &str, account_number: &str) -> Result<Iban, Error> {
+    let clearing_number = clearing_number.trim();
+    if !(3..=5).contains(&clearing_number.len()) || !clearing_number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Error::InvalidClearingNumber(clearing_number.to_string()));
+    }
+    let account: String = account_number
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '/'))
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if account.is_empty() || account.len() > 12 || !account.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(Error::InvalidAccountNumber(account_number.to_string()));
+    }
+    swiss_iban(&format!("{clearing_number:0>5}"), &account)
+}
+
+/// Assemble a `CH` IBAN from a 5 digit IID and an account part of up to 12
+/// characters
+fn swiss_iban(iid: &str, account: &str) -> Result<Iban, Error> {
+    let bban = format!("{iid}{account:0>12}");
+    let check_digits = checkdigit::mod97_10(&format!("{bban}CH"))
+        .ok_or_else(|| Error::InvalidIban(bban.clone()))?;
+    let iban = format!("CH{check_digits:02}{bban}");
+    iban.parse().map_err(|_| Error::InvalidIban(iban))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iban::IbanLike;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case("80-470-3"   , "CH1809000000800004703")]
+    #[case("01-12345-3" , "CH2709000000010123453")]
+    #[case(" 60-9-9 "   , "CH7809000000600000099")]
+    fn postal_account(#[case] account: &str, #[case] expected: &str) {
+        assert_eq!(postal_account_to_iban(account).unwrap().electronic_str(), expected);
+    }
+
+    #[rstest]
+    #[case("80-470-4"    , Error::InvalidChecksum { expected: 3, found: 4 })]
+    #[case("80-4703"     , Error::InvalidPostalAccount("80-4703".into()))]
+    #[case("8-470-3"     , Error::InvalidPostalAccount("8-470-3".into()))]
+    #[case("80-1234567-3", Error::InvalidPostalAccount("80-1234567-3".into()))]
+    #[case("80-47a-3"    , Error::InvalidPostalAccount("80-47a-3".into()))]
+    #[case(""            , Error::InvalidPostalAccount("".into()))]
+    fn postal_account_rejects(#[case] account: &str, #[case] expected: Error) {
+        assert_eq!(postal_account_to_iban(account).unwrap_err(), expected);
+    }
+
+    #[rstest]
+    #[case("762"  , "1162-3852.957", "CH9300762011623852957")]
+    #[case("00762", "011623852957" , "CH9300762011623852957")]
+    fn clearing(#[case] clearing_number: &str, #[case] account: &str, #[case] expected: &str) {
+        assert_eq!(clearing_to_iban(clearing_number, account).unwrap().electronic_str(), expected);
+    }
+
+    #[rstest]
+    #[case("76"    , "1")]
+    #[case("123456", "1")]
+    #[case("762"   , "")]
+    #[case("762"   , "1234567890123")]
+    #[case("762"   , "12_34")]
+    fn clearing_rejects(#[case] clearing_number: &str, #[case] account: &str) {
+        assert!(clearing_to_iban(clearing_number, account).is_err());
+    }
+}
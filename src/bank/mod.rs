@@ -0,0 +1,97 @@
+//! An excerpt of the SIX bank master data, to look up the bank behind the
+//! institution identification (IID) of a Swiss or Liechtenstein IBAN.
+//!
+//! With this feature, [`QRBill::new`](crate::QRBill::new) rejects accounts
+//! whose IID is not in the directory.
+//!
+//! The directory is generated from `src/bank/bankmaster-excerpt.csv`, which
+//! only lists a handful of large banks and PostFinance. Before enabling the
+//! feature, replace it with a full, current copy of the bank master CSV:
+//! `cargo run --example update-bank-directory -- bankmaster.csv`.
+
+use iban::IbanLike;
+
+use crate::{Iban, IBAN_ALLOWED_COUNTRIES};
+
+mod directory;
+
+/// A financial institution from the bank master data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bank {
+    /// Institution identification, the digits 5 to 9 of the IBAN
+    pub iid: u32,
+    pub bic: Option<&'static str>,
+    /// Whether the IID is a QR-IID, used in QR-IBANs for payments with a QR
+    /// reference
+    pub qr_iid: bool,
+    pub name: &'static str,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("Only IBANs from CH or LI have an IID, but one from {0} was given.")]
+    UnsupportedCountry(String),
+    #[error("The IID {0} is not in the bank master data.")]
+    UnknownIid(u32),
+}
+
+/// Look up the bank with the given IID
+pub fn lookup(iid: u32) -> Option<&'static Bank> {
+    directory::BANKS
+        .binary_search_by_key(&iid, |bank| bank.iid)
+        .ok()
+        .map(|i| &directory::BANKS[i])
+}
+
+/// Look up the bank an IBAN belongs to, if its IID is in the directory
+pub fn for_iban(iban: &Iban) -> Result<Option<&'static Bank>, Error> {
+    if !IBAN_ALLOWED_COUNTRIES.contains(&iban.country_code()) {
+        return Err(Error::UnsupportedCountry(iban.country_code().to_string()));
+    }
+    Ok(lookup(crate::iid(iban)))
+}
+
+/// Look up the bank an IBAN belongs to, rejecting IIDs not in the directory
+pub fn validate_iid(iban: &Iban) -> Result<&'static Bank, Error> {
+    for_iban(iban)?.ok_or_else(|| Error::UnknownIid(crate::iid(iban)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn directory_is_sorted_and_unique() {
+        assert!(directory::BANKS.windows(2).all(|pair| pair[0].iid < pair[1].iid));
+    }
+
+    #[rstest]
+    #[case("CH0300700110000123456", 700  , false)]
+    #[case("CH1809000000800004703", 9000 , false)]
+    #[case("CH1430000000800004703", 30000, true )]
+    fn known(#[case] iban: &str, #[case] iid: u32, #[case] qr_iid: bool) {
+        let bank = for_iban(&iban.parse().unwrap()).unwrap().unwrap();
+        assert_eq!(bank.iid, iid);
+        assert_eq!(bank.qr_iid, qr_iid);
+        assert_eq!(lookup(iid), Some(bank));
+        assert_eq!(validate_iid(&iban.parse().unwrap()), Ok(bank));
+    }
+
+    #[rstest]
+    #[case("CH4431999123000889012", 31999)]
+    #[case("CH9300762011623852957",   762)]
+    fn unknown(#[case] iban: &str, #[case] iid: u32) {
+        let iban = iban.parse().unwrap();
+        assert_eq!(for_iban(&iban), Ok(None));
+        assert_eq!(validate_iid(&iban), Err(Error::UnknownIid(iid)));
+    }
+
+    #[test]
+    fn rejects_foreign_ibans() {
+        let iban = "DE89370400440532013000".parse().unwrap();
+        assert_eq!(for_iban(&iban), Err(Error::UnsupportedCountry("DE".into())));
+        assert_eq!(validate_iid(&iban), Err(Error::UnsupportedCountry("DE".into())));
+    }
+}
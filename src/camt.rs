@@ -0,0 +1,395 @@
+//! Reading ISO 20022 camt.053 statements and camt.054 notifications from the
+//! bank of the creditor, and matching the payments in them to issued bills.
+
+use chrono::NaiveDate;
+
+use crate::{QRBill, Reference};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("The document is not valid XML: {0}")]
+    Xml(String),
+    #[error("The document is neither a camt.053 statement nor a camt.054 notification.")]
+    UnknownDocument,
+    #[error("An entry has an invalid amount '{0}'.")]
+    InvalidAmount(String),
+    #[error("An entry has no credit or debit indicator (CdtDbtInd).")]
+    MissingCreditDebit,
+    #[error("An entry has an invalid credit or debit indicator '{0}'.")]
+    InvalidCreditDebit(String),
+}
+
+/// Whether money was received or sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreditDebit {
+    Credit,
+    Debit,
+}
+
+/// A single transaction of an entry in a statement or notification
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payment {
+    pub amount: f64,
+    /// ISO 4217 code, e.g. `CHF`
+    pub currency: String,
+    pub credit_debit: CreditDebit,
+    pub booking_date: Option<NaiveDate>,
+    /// The structured creditor reference, QRR or SCOR, without spaces
+    pub reference: Option<String>,
+}
+
+/// Extract the transactions of all entries of a camt.053 or camt.054
+/// document, of any version.
+///
+/// An entry without transaction details counts as a single transaction, as
+/// does one whose several transactions do not all have their own amount: it
+/// cannot be told which reference goes with what part of the entry, so its
+/// payment has no reference. Only booked entries are read: pending and informational entries, and
+/// those reversing an earlier entry, are skipped.
+pub fn parse(xml: &str) -> Result<Vec<Payment>, Error> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| Error::Xml(e.to_string()))?;
+    let root = document.root_element();
+    if !root.children().any(|n| matches!(n.tag_name().name(), "BkToCstmrStmt" | "BkToCstmrDbtCdtNtfctn")) {
+        return Err(Error::UnknownDocument);
+    }
+
+    let mut payments = vec![];
+    for entry in root.descendants().filter(|n| n.has_tag_name("Ntry")) {
+        if !is_booked(entry) || is_reversal(entry) {
+            continue;
+        }
+        let entry_amount = child(entry, "Amt");
+        let entry_credit_debit = child(entry, "CdtDbtInd").and_then(|n| n.text());
+        let booking_date = child(entry, "BookgDt")
+            .and_then(|n| child(n, "Dt").or_else(|| child(n, "DtTm")))
+            .and_then(|n| n.text())
+            .and_then(|t| NaiveDate::parse_from_str(t.get(..10).unwrap_or(t), "%Y-%m-%d").ok());
+
+        let transactions: Vec<_> = entry.descendants().filter(|n| n.has_tag_name("TxDtls")).collect();
+        let amounts: Vec<_> = match transactions.as_slice() {
+            [transaction] => vec![transaction_amount(*transaction).or(entry_amount)],
+            _ => transactions.iter().map(|transaction| transaction_amount(*transaction)).collect(),
+        };
+        if amounts.is_empty() || amounts.contains(&None) {
+            if let Some(amount) = entry_amount {
+                payments.push(payment(amount, entry_credit_debit, booking_date, None)?);
+            }
+            continue;
+        }
+        for (transaction, amount) in transactions.into_iter().zip(amounts.into_iter().flatten()) {
+            let credit_debit = child(transaction, "CdtDbtInd").and_then(|n| n.text()).or(entry_credit_debit);
+            let reference = path(transaction, &["RmtInf", "Strd", "CdtrRefInf", "Ref"])
+                .and_then(|n| n.text())
+                .map(|r| r.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase());
+            payments.push(payment(amount, credit_debit, booking_date, reference)?);
+        }
+    }
+    Ok(payments)
+}
+
+fn transaction_amount<'a, 'input>(transaction: roxmltree::Node<'a, 'input>) -> Option<roxmltree::Node<'a, 'input>> {
+    child(transaction, "Amt").or_else(|| path(transaction, &["AmtDtls", "TxAmt", "Amt"]))
+}
+
+/// Whether the status of `entry` is `BOOK`: given as `<Sts><Cd>BOOK</Cd></Sts>`
+/// from camt.053.001.08 and camt.054.001.08 on, and as `<Sts>BOOK</Sts>` before
+fn is_booked(entry: roxmltree::Node) -> bool {
+    child(entry, "Sts")
+        .and_then(|status| child(status, "Cd").or(Some(status)))
+        .and_then(|n| n.text())
+        .is_some_and(|status| status.trim() == "BOOK")
+}
+
+fn is_reversal(entry: roxmltree::Node) -> bool {
+    child(entry, "RvslInd")
+        .and_then(|n| n.text())
+        .is_some_and(|reversal| matches!(reversal.trim(), "true" | "1"))
+}
+
+fn payment(
+    amount: roxmltree::Node,
+    credit_debit: Option<&str>,
+    booking_date: Option<NaiveDate>,
+    reference: Option<String>,
+) -> Result<Payment, Error> {
+    let text = amount.text().unwrap_or_default().trim();
+    Ok(Payment {
+        amount: text.parse().map_err(|_| Error::InvalidAmount(text.to_string()))?,
+        currency: amount.attribute("Ccy").unwrap_or_default().to_string(),
+        credit_debit: match credit_debit.map(str::trim) {
+            Some("CRDT") => CreditDebit::Credit,
+            Some("DBIT") => CreditDebit::Debit,
+            Some(other) => return Err(Error::InvalidCreditDebit(other.to_string())),
+            None => return Err(Error::MissingCreditDebit),
+        },
+        booking_date,
+        reference,
+    })
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn path<'a, 'input>(node: roxmltree::Node<'a, 'input>, names: &[&str]) -> Option<roxmltree::Node<'a, 'input>> {
+    names.iter().try_fold(node, |node, name| child(node, name))
+}
+
+/// How far a bill has been paid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Unpaid,
+    Paid,
+    Partial { outstanding: f64 },
+    Over { excess: f64 },
+}
+
+/// The payments received for one bill
+#[derive(Debug)]
+pub struct BillPayments<'a> {
+    pub bill: &'a QRBill,
+    pub payments: Vec<Payment>,
+    pub status: Status,
+}
+
+#[derive(Debug)]
+pub struct Reconciliation<'a> {
+    /// All bills with a reference, in the given order
+    pub bills: Vec<BillPayments<'a>>,
+    /// Credits which match no bill by reference and currency
+    pub unmatched: Vec<Payment>,
+}
+
+/// Match credited `payments` to `bills` by their QRR or SCOR reference and
+/// currency, and compare the sum paid to the amount of each bill. Bills
+/// without an amount are paid by any payment. Debits are ignored.
+pub fn reconcile<'a>(bills: &'a [QRBill], payments: &[Payment]) -> Reconciliation<'a> {
+    let mut reconciliation = Reconciliation {
+        bills: bills
+            .iter()
+            .filter(|bill| !matches!(bill.reference, Reference::None))
+            .map(|bill| BillPayments { bill, payments: vec![], status: Status::Unpaid })
+            .collect(),
+        unmatched: vec![],
+    };
+    for payment in payments.iter().filter(|p| p.credit_debit == CreditDebit::Credit) {
+        let matching = reconciliation.bills.iter_mut().find(|b| {
+            payment.reference.as_deref() == Some(reference_key(&b.bill.reference).as_str())
+                && payment.currency == b.bill.currency.to_string()
+        });
+        match matching {
+            Some(b) => b.payments.push(payment.clone()),
+            None => reconciliation.unmatched.push(payment.clone()),
+        }
+    }
+    for b in &mut reconciliation.bills {
+        let paid = crate::swico::round_to_centime(b.payments.iter().map(|p| p.amount).sum());
+        b.status = match b.bill.amount {
+            _ if b.payments.is_empty() => Status::Unpaid,
+            None => Status::Paid,
+            Some(amount) if paid < amount - 0.005 => Status::Partial {
+                outstanding: crate::swico::round_to_centime(amount - paid),
+            },
+            Some(amount) if paid > amount + 0.005 => Status::Over {
+                excess: crate::swico::round_to_centime(paid - amount),
+            },
+            Some(_) => Status::Paid,
+        };
+    }
+    reconciliation
+}
+
+/// The reference as it appears in camt documents
+fn reference_key(reference: &Reference) -> String {
+    match reference {
+        Reference::Qrr(esr) => esr.to_raw(),
+        Reference::Scor(reference) => reference.with_checksum(),
+        Reference::None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{esr::Esr, iso11649::Iso11649, tests::options, QRBillOptions};
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    const CAMT054: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.054.001.08">
+  <BkToCstmrDbtCdtNtfctn>
+    <Ntfctn>
+      <Ntry>
+        <Amt Ccy="CHF">2949.75</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2024-03-04</Dt></BookgDt>
+        <NtryDtls>
+          <TxDtls>
+            <Amt Ccy="CHF">1949.75</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <RmtInf><Strd><CdtrRefInf>
+              <Tp><CdOrPrtry><Prtry>QRR</Prtry></CdOrPrtry></Tp>
+              <Ref>210000000003139471430009017</Ref>
+            </CdtrRefInf></Strd></RmtInf>
+          </TxDtls>
+          <TxDtls>
+            <AmtDtls><TxAmt><Amt Ccy="CHF">1000.00</Amt></TxAmt></AmtDtls>
+            <RmtInf><Strd><CdtrRefInf>
+              <Tp><CdOrPrtry><Cd>SCOR</Cd></CdOrPrtry></Tp>
+              <Ref>RF18 5390 0754 7034</Ref>
+            </CdtrRefInf></Strd></RmtInf>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="CHF">12.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>BOOK</Cd></Sts>
+        <BookgDt><Dt>2024-03-05</Dt></BookgDt>
+      </Ntry>
+    </Ntfctn>
+  </BkToCstmrDbtCdtNtfctn>
+</Document>"#;
+
+    fn date(d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2024, 3, d)
+    }
+
+    fn credit(amount: f64, reference: Option<&str>) -> Payment {
+        Payment {
+            amount,
+            currency: "CHF".into(),
+            credit_debit: CreditDebit::Credit,
+            booking_date: date(4),
+            reference: reference.map(str::to_string),
+        }
+    }
+
+    fn bill(reference: Reference, amount: Option<f64>) -> QRBill {
+        QRBill::new(QRBillOptions { reference, amount, ..options("Max Muster".into(), vec![]) }).unwrap()
+    }
+
+    #[test]
+    fn parse_camt054() {
+        assert_eq!(parse(CAMT054).unwrap(), vec![
+            credit(1949.75, Some("210000000003139471430009017")),
+            credit(1000.0 , Some("RF18539007547034")),
+            Payment { credit_debit: CreditDebit::Debit, booking_date: date(5), ..credit(12.0, None) },
+        ]);
+    }
+
+    #[test]
+    fn parse_camt053() {
+        let camt053 = CAMT054
+            .replace("camt.054.001.08", "camt.053.001.08")
+            .replace("BkToCstmrDbtCdtNtfctn", "BkToCstmrStmt")
+            .replace("Ntfctn", "Stmt");
+        assert_eq!(parse(&camt053).unwrap().len(), 3);
+    }
+
+    /// A camt.054 notification with a single entry of 10.00 CHF, made of
+    /// `entry_elements`
+    fn single_entry(entry_elements: &str) -> String {
+        format!(r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.054.001.04">
+  <BkToCstmrDbtCdtNtfctn><Ntfctn><Ntry>
+    <Amt Ccy="CHF">10.00</Amt>
+    {entry_elements}
+  </Ntry></Ntfctn></BkToCstmrDbtCdtNtfctn>
+</Document>"#)
+    }
+
+    #[rstest]
+    #[case("<CdtDbtInd>CRDT</CdtDbtInd><Sts><Cd>BOOK</Cd></Sts>"                       , 1)]
+    #[case("<CdtDbtInd>CRDT</CdtDbtInd><Sts>BOOK</Sts>"                                , 1)]
+    #[case("<CdtDbtInd>CRDT</CdtDbtInd><Sts><Cd>PDNG</Cd></Sts>"                       , 0)]
+    #[case("<CdtDbtInd>CRDT</CdtDbtInd><Sts>INFO</Sts>"                                , 0)]
+    #[case("<CdtDbtInd>CRDT</CdtDbtInd>"                                               , 0)]
+    #[case("<CdtDbtInd>CRDT</CdtDbtInd><RvslInd>false</RvslInd><Sts>BOOK</Sts>"        , 1)]
+    #[case("<CdtDbtInd>DBIT</CdtDbtInd><RvslInd>true</RvslInd><Sts><Cd>BOOK</Cd></Sts>", 0)]
+    fn parse_only_booked_entries(#[case] entry_elements: &str, #[case] expected_payments: usize) {
+        assert_eq!(parse(&single_entry(entry_elements)).unwrap().len(), expected_payments);
+    }
+
+    #[rstest]
+    #[case("<BookgDt><Dt>2024-03-04</Dt></BookgDt>"                    , date(4))]
+    #[case("<BookgDt><DtTm>2024-03-04T09:30:00+01:00</DtTm></BookgDt>", date(4))]
+    #[case(""                                                           , None   )]
+    fn booking_date(#[case] booking_date_element: &str, #[case] expected: Option<NaiveDate>) {
+        let xml = single_entry(&format!("<CdtDbtInd>CRDT</CdtDbtInd><Sts>BOOK</Sts>{booking_date_element}"));
+        assert_eq!(parse(&xml).unwrap()[0].booking_date, expected);
+    }
+
+    #[test]
+    fn transactions_without_amounts() {
+        let reference = |r: &str| format!("<TxDtls><RmtInf><Strd><CdtrRefInf><Ref>{r}</Ref></CdtrRefInf></Strd></RmtInf></TxDtls>");
+        let xml = single_entry(&format!(
+            "<CdtDbtInd>CRDT</CdtDbtInd><Sts>BOOK</Sts><NtryDtls>{}{}</NtryDtls>",
+            reference("RF18539007547034"),
+            reference("210000000003139471430009017"),
+        ));
+        assert_eq!(parse(&xml).unwrap(), [Payment { booking_date: None, ..credit(10.0, None) }]);
+    }
+
+    #[rstest]
+    #[case("<Document/>", Error::UnknownDocument)]
+    #[case("<Document>" , Error::Xml("the root node was opened but never closed".into()))]
+    fn parse_rejects(#[case] xml: &str, #[case] expected: Error) {
+        assert_eq!(parse(xml).unwrap_err(), expected);
+    }
+
+    #[rstest]
+    #[case("<Sts>BOOK</Sts>"                           , Error::MissingCreditDebit)]
+    #[case("<CdtDbtInd>CRED</CdtDbtInd><Sts>BOOK</Sts>", Error::InvalidCreditDebit("CRED".into()))]
+    fn parse_rejects_entry(#[case] entry_elements: &str, #[case] expected: Error) {
+        assert_eq!(parse(&single_entry(entry_elements)).unwrap_err(), expected);
+    }
+
+    #[test]
+    fn reconcile_payments() {
+        let qrr = |n: &str| Reference::Qrr(Esr::generate(None, "1", n).unwrap());
+        let bills = vec![
+            bill(Reference::Scor(Iso11649::new("539007547034")), Some(1000.0)),
+            bill(qrr("1"), Some(100.0)),
+            bill(qrr("2"), Some(100.0)),
+            bill(qrr("3"), None),
+            bill(qrr("4"), Some(100.0)),
+            bill(Reference::None, Some(100.0)),
+        ];
+        let key = |i: usize| reference_key(&bills[i].reference);
+        let payments = vec![
+            credit(1000.0, Some("RF18539007547034")),
+            credit(60.0, Some(&key(1))),
+            credit(150.0, Some(&key(2))),
+            credit(5.0, Some(&key(3))),
+            credit(40.0, Some(&key(1))),
+            credit(30.0, Some(&key(4))),
+            credit(7.0, None),
+            Payment { credit_debit: CreditDebit::Debit, ..credit(100.0, Some(&key(4))) },
+            Payment { currency: "EUR".into(), ..credit(70.0, Some(&key(4))) },
+        ];
+
+        let reconciliation = reconcile(&bills, &payments);
+        let statuses: Vec<_> = reconciliation.bills.iter().map(|b| b.status).collect();
+        assert_eq!(statuses, vec![
+            Status::Paid,
+            Status::Paid,
+            Status::Over { excess: 50.0 },
+            Status::Paid,
+            Status::Partial { outstanding: 70.0 },
+        ]);
+        assert_eq!(reconciliation.bills[1].payments.len(), 2);
+        assert_eq!(reconciliation.unmatched, vec![
+            credit(7.0, None),
+            Payment { currency: "EUR".into(), ..credit(70.0, Some(&key(4))) },
+        ]);
+    }
+
+    #[test]
+    fn reconcile_unpaid() {
+        let bills = vec![bill(Reference::Scor(Iso11649::new("1")), Some(10.0))];
+        let reconciliation = reconcile(&bills, &[]);
+        assert_eq!(reconciliation.bills[0].status, Status::Unpaid);
+        assert!(format!("{reconciliation:?}").contains("Unpaid"));
+    }
+}
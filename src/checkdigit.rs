@@ -0,0 +1,139 @@
+//! Check digit algorithms used in Swiss payment traffic.
+//!
+//! All functions work on borrowed text without allocating, and return `None`
+//! when given characters, or a modulus, the algorithm is not defined for.
+
+/// Modulo 10 recursive check digit of `digits`.
+///
+/// Used for QR references (see `esr`), ISR references and the participant
+/// numbers of ISR and postal accounts.
+pub fn mod10_recursive(digits: &str) -> Option<u8> {
+    const TABLE: [u8; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+    let mut carry = 0;
+    for c in digits.chars() {
+        let digit = c.to_digit(10)? as u8;
+        carry = TABLE[((digit + carry) % 10) as usize];
+    }
+    Some((10 - carry) % 10)
+}
+
+/// Remainder of dividing the number represented by `text` by `modulus`.
+///
+/// As in ISO 7064, digits stand for themselves and letters, in either case,
+/// for the two digits 10 (A) to 35 (Z). Returns `None` for a `modulus` of 0.
+pub fn remainder(text: &str, modulus: u32) -> Option<u32> {
+    remainder_of_chars(text.chars(), modulus)
+}
+
+fn remainder_of_chars(chars: impl Iterator<Item = char>, modulus: u32) -> Option<u32> {
+    if modulus == 0 {
+        return None;
+    }
+    let modulus = modulus as u64;
+    let mut remainder = 0u64;
+    for c in chars {
+        let value = c.to_digit(36)? as u64;
+        let shift = if value < 10 { 10 } else { 100 };
+        remainder = (remainder * shift + value) % modulus;
+    }
+    Some(remainder as u32)
+}
+
+/// The two ISO 7064 MOD 97-10 check digits for `text`.
+///
+/// These are calculated as if they were appended to `text` as `00`. Where the
+/// check digits are printed in front, as in IBANs and creditor references,
+/// `text` must already be rearranged accordingly: for a creditor reference
+/// `RFxx1234` it is `1234RF`.
+pub fn mod97_10(text: &str) -> Option<u8> {
+    let remainder = remainder_of_chars(text.chars().chain("00".chars()), 97)?;
+    Some((98 - remainder) as u8)
+}
+
+/// Whether `text`, ending in its MOD 97-10 check digits, is valid
+pub fn is_valid_mod97_10(text: &str) -> bool {
+    remainder(text, 97) == Some(1)
+}
+
+/// Modulo 11 check digit of the 8 digits of a Swiss business identification
+/// number (UID), such as `10932255` for `CHE-109.322.551`.
+///
+/// Returns `None` if `digits` are not exactly 8 digits, or if their check
+/// digit would be 10: such numbers are never issued.
+pub fn uid_mod11(digits: &str) -> Option<u8> {
+    const WEIGHTS: [u32; 8] = [5, 4, 3, 2, 7, 6, 5, 4];
+    let mut sum = 0;
+    let mut count = 0;
+    for (c, weight) in digits.chars().zip(WEIGHTS.iter().chain(std::iter::repeat(&0))) {
+        sum += c.to_digit(10)? * weight;
+        count += 1;
+    }
+    if count != WEIGHTS.len() {
+        return None;
+    }
+    match 11 - sum % 11 {
+        11 => Some(0),
+        10 => None,
+        check => Some(check as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case(""                          , Some(0))]
+    #[case("0"                         , Some(0))]
+    #[case("21000000000313947143000901", Some(7))]
+    #[case("00000000000000000012345678", Some(6))]
+    #[case("12a"                       , None   )]
+    #[case("٣"                         , None   )]
+    fn mod10_recursive_check_digit(#[case] digits: &str, #[case] expected: Option<u8>) {
+        assert_eq!(mod10_recursive(digits), expected);
+    }
+
+    #[rstest]
+    #[case("0"                  , 97, Some(0) )]
+    #[case("193"                , 97, Some(96))]
+    #[case("A"                  , 97, Some(10))]
+    #[case("RF"                 , 97, Some(2715 % 97))]
+    #[case("rf"                 , 97, Some(2715 % 97))]
+    #[case("97000000000000000000000000000000000000000000001", 97, Some(1))]
+    #[case("-"                  , 97, None    )]
+    #[case("193"                ,  0, None    )]
+    #[case(""                   ,  0, None    )]
+    fn remainder_of_text(#[case] text: &str, #[case] modulus: u32, #[case] expected: Option<u32>) {
+        assert_eq!(remainder(text, modulus), expected);
+    }
+
+    #[rstest]
+    #[case("539007547034RF", Some(18))] // RF18 5390 0754 7034
+    #[case("ARF"           , Some(25))] // RF25 A
+    #[case("00762011623852957CH", Some(93))] // CH93 0076 2011 6238 5295 7
+    #[case("5390 0754"     , None    )]
+    fn mod97_10_check_digits(#[case] text: &str, #[case] expected: Option<u8>) {
+        assert_eq!(mod97_10(text), expected);
+    }
+
+    #[rstest]
+    #[case("00762011623852957CH93", true )]
+    #[case("00762011623852957CH94", false)]
+    #[case("539007547034RF18"     , true )]
+    #[case(""                     , false)]
+    fn valid_mod97_10(#[case] text: &str, #[case] expected: bool) {
+        assert_eq!(is_valid_mod97_10(text), expected);
+    }
+
+    #[rstest]
+    #[case("10932255" , Some(1))] // CHE-109.322.551
+    #[case("11628171" , Some(0))] // CHE-116.281.710
+    #[case("1093225"  , None   )]
+    #[case("109322551", None   )]
+    #[case("1093225x" , None   )]
+    fn uid_check_digit(#[case] digits: &str, #[case] expected: Option<u8>) {
+        assert_eq!(uid_mod11(digits), expected);
+    }
+}
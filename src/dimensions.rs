@@ -87,7 +87,6 @@ pub struct Dimensions {
     // Dimensions of blank rectangles
     pub blank_payable:  Xy,
     pub blank_amount:   Xy,
-    pub max_chars_line: usize,
 }
 
 const RCT_X: f64 =   5.0; // mm x-position of RECEIPT part sections
@@ -103,27 +102,59 @@ pub fn receipt() -> Dimensions { Dimensions {
         acceptance:  Some(Xy::mm(ACC_E, 82.0)),
         qr_code:     None,
         alt_proc:    None,
+        //                     width  height
+        information_size: Xy::mm(52.0, 56.0),
     },
 
     // The font sizes for the receipt are 6 pt for the headings (bold) and 8 pt
     // for the associated values. The exception, in font size 11 pt (bold), is
     // the title "Receipt".
     font: Fonts {           //    size  line-spacing
-        title:              font( 11.0, 11.0), // bold
-        heading:            font(  6.0,  9.0), // bold
+        title:              bold( 11.0, 11.0),
+        heading:            bold(  6.0,  9.0),
         value:              font(  8.0,  9.0),
-        amount:             font(  8.0, 11.0),
-        acceptance_pt: Some(font(  6.0,  8.0)), // bold
+        acceptance_pt: Some(bold(  6.0,  8.0)),
         alt_proc:      None,
     },
 
     blank_payable: Xy::mm( 52.0, 20.0),
     blank_amount:  Xy::mm( 30.0, 10.0),
-
-    max_chars_line: 38,
 }}
 
-pub fn payment() -> Dimensions { Dimensions {
+/// The font size of the values in the "Amount" and "Details" sections of the
+/// payment part. Their headings are always printed 2 pt smaller.
+///
+/// Both must lie between 6 and 10 pt, which leaves values between 8 and 10 pt.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct FontSize { value_in_pt: f64 }
+
+impl FontSize {
+    /// The recommended sizes: 10 pt for values and 8 pt for headings
+    pub const RECOMMENDED: Self = Self { value_in_pt: 10.0 };
+
+    /// The smallest permitted sizes: 8 pt for values and 6 pt for headings
+    pub const SMALLEST: Self = Self { value_in_pt: 8.0 };
+
+    pub fn new(value_in_pt: f64) -> Result<Self, crate::Error> {
+        if !(Self::SMALLEST.value_in_pt..=Self::RECOMMENDED.value_in_pt).contains(&value_in_pt) {
+            return Err(crate::Error::FontSize);
+        }
+        Ok(Self { value_in_pt })
+    }
+
+    pub fn value_in_pt  (self) -> f64 { self.value_in_pt       }
+    pub fn heading_in_pt(self) -> f64 { self.value_in_pt - 2.0 }
+}
+
+impl Default for FontSize {
+    fn default() -> Self { Self::RECOMMENDED }
+}
+
+pub fn payment() -> Dimensions { payment_with_font_size(FontSize::RECOMMENDED) }
+
+/// Dimensions of the payment part with values and headings printed at
+/// `font_size`. Line spacing is 1 pt more than the font size.
+pub fn payment_with_font_size(size: FontSize) -> Dimensions { Dimensions {
     section: Sections {
         title:            Xy::mm(PAY_X,  5.0),
         information:      Xy::mm(INF_X,  5.0),
@@ -131,6 +162,8 @@ pub fn payment() -> Dimensions { Dimensions {
         acceptance:  None,
         qr_code:     Some(Xy::mm(PAY_X, 17.0)),
         alt_proc:    Some(Xy::mm(PAY_X, 90.0)),
+        //                     width  height
+        information_size: Xy::mm(87.0, 85.0),
     },
 
     // The font size for headings and their associated values on the payment
@@ -148,19 +181,16 @@ pub fn payment() -> Dimensions { Dimensions {
     //
     // When filling in the "Alternative procedures" element, the font size is 7
     // pt, with the name of the alternative procedure printed in bold type.
-    font: Fonts {           //    size  line-spacing
-        title:              font( 11.0, 11.0), // bold
-        heading:            font(  8.0, 11.0), // bold
-        value:              font( 10.0, 11.0),
-        amount:             font( 10.0, 13.0),
+    font: Fonts {           //    size                  line-spacing
+        title:              bold( 11.0                , 11.0                    ),
+        heading:            bold(size.heading_in_pt() , size.value_in_pt() + 1.0),
+        value:              font(size.value_in_pt()   , size.value_in_pt() + 1.0),
         acceptance_pt: None,
-        alt_proc:      Some(font(  7.0,  8.0)), // bold & normal
+        alt_proc:      Some(font(  7.0                ,  8.0                    )), // bold & normal
     },
 
     blank_payable: Xy::mm( 65.0, 25.0),
     blank_amount:  Xy::mm( 40.0, 15.0),
-
-    max_chars_line: 72,
 }}
 
 pub struct Sections {
@@ -170,27 +200,39 @@ pub struct Sections {
     pub acceptance:   Option<Xy>,
     pub qr_code:      Option<Xy>,
     pub alt_proc:     Option<Xy>,
+    pub information_size: Xy,
 }
 
 pub struct Fonts {
     pub title:                Font,
     pub heading:              Font,
     pub value:                Font,
-    pub amount:               Font,
     pub acceptance_pt: Option<Font>,
     pub alt_proc:      Option<Font>,
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct Font { pub (crate) size: Length, pub (crate) line_spacing: Length }
+pub struct Font {
+    pub (crate) size: Length,
+    pub (crate) line_spacing: Length,
+    pub (crate) weight: Weight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weight { Normal, Bold }
 
 fn font(size_in_pt: f64, line_spacing_in_pt: f64) -> Font {
     Font {
         size:         Length::pt(size_in_pt),
         line_spacing: Length::pt(line_spacing_in_pt),
+        weight:       Weight::Normal,
     }
 }
 
+fn bold(size_in_pt: f64, line_spacing_in_pt: f64) -> Font {
+    Font { weight: Weight::Bold, ..font(size_in_pt, line_spacing_in_pt) }
+}
+
 pub mod blank_rectangle {
     use super::*;
     pub fn line_length() -> Length { Length::mm(3.0 ) }
@@ -198,33 +240,42 @@ pub mod blank_rectangle {
     
 }
 
-pub fn make_svg_styles() -> String {
-    let r = receipt().font;
-    let p = payment().font;
+/// Font size of the note above the top line, in pt
+const SEPARATE_NOTE_FONT_SIZE: f64 = 7.0;
+
+/// Generate the CSS for the text classes, from the fonts of the receipt and
+/// the payment part actually being rendered.
+pub fn make_svg_styles(r: &Fonts, p: &Fonts) -> String {
+    // Sizes are only ever set in steps of a tenth of a point: round away the
+    // noise of the pt -> uu -> pt round trip.
+    let pt = |font: Font| (font.size.as_pt() * 10.0).round() / 10.0;
 
-    let r_titl = r.title                 .size.as_pt();
-    let r_head = r.heading               .size.as_pt();
-    let r_valu = r.value                 .size.as_pt();
-    let r_acpt = r.acceptance_pt.unwrap().size.as_pt();
+    let r_titl = pt(r.title                 );
+    let r_head = pt(r.heading               );
+    let r_valu = pt(r.value                 );
+    let r_acpt = pt(r.acceptance_pt.unwrap());
 
-    let p_titl = p.title                 .size.as_pt();
-    let p_head = p.heading               .size.as_pt();
-    let p_valu = p.value                 .size.as_pt();
-    let p_altp = p.alt_proc     .unwrap().size.as_pt();
+    let p_titl = pt(p.title                 );
+    let p_head = pt(p.heading               );
+    let p_valu = pt(p.value                 );
+    let p_altp = pt(p.alt_proc     .unwrap());
+
+    let s_note = SEPARATE_NOTE_FONT_SIZE;
 
     format!("
     text {{
          font-family: Arial, Helvetica, Frutiger, \"Liberation Sans\", sans-serif;
     }}
-    .r-title         {{ font-size: {r_titl:2.0}pt; font-weight: bold; }}
-    .r-heading       {{ font-size: {r_head:2.0}pt; font-weight: bold; }}
-    .r-value         {{ font-size: {r_valu:2.0}pt;                    }}
-    .r-acceptance-pt {{ font-size: {r_acpt:2.0}pt; font-weight: bold; }}
-
-    .p-title         {{ font-size: {p_titl:2.0}pt; font-weight: bold; }}
-    .p-heading       {{ font-size: {p_head:2.0}pt; font-weight: bold; }}
-    .p-value         {{ font-size: {p_valu:2.0}pt;                    }}
-    .p-alt-proc      {{ font-size: {p_altp:2.0}pt; font-weight: bold; }}
-    }}
+    .r-title         {{ font-size: {r_titl}pt; font-weight: bold; }}
+    .r-heading       {{ font-size: {r_head}pt; font-weight: bold; }}
+    .r-value         {{ font-size: {r_valu}pt;                    }}
+    .r-acceptance-pt {{ font-size: {r_acpt}pt; font-weight: bold; }}
+
+    .p-title         {{ font-size: {p_titl}pt; font-weight: bold; }}
+    .p-heading       {{ font-size: {p_head}pt; font-weight: bold; }}
+    .p-value         {{ font-size: {p_valu}pt;                    }}
+    .p-alt-proc      {{ font-size: {p_altp}pt; font-weight: bold; }}
+
+    .separate-note   {{ font-size: {s_note}pt;                    }}
 ")
 }
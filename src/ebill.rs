@@ -0,0 +1,224 @@
+//! The eBill alternative procedure, which lets the debtor receive the bill in
+//! their e-banking: `eBill/B/peter@sample.ch` or
+//! `eBill/B/41010560425610173/R123`.
+
+use crate::{uid::Uid, MAX_ALTERNATIVE_PROCEDURE_CHARS};
+
+const PREFIX: &str = "eBill";
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("An eBill alternative procedure must start with 'eBill/'.")]
+    InvalidPrefix,
+    #[error("The eBill type must be B or P, but '{0}' was given.")]
+    InvalidKind(String),
+    #[error("The eBill recipient must be an email address, an E-bill ID of 17 digits or a UID, but '{0}' was given.")]
+    InvalidRecipient(String),
+    #[error("The eBill reference ID must be 1 to 35 letters and digits, but '{0}' was given.")]
+    InvalidReferenceId(String),
+    #[error("An alternative procedure may have at most 100 characters, but this one has {0}.")]
+    TooLong(usize),
+}
+
+/// Whom the bill is addressed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// `B`: a business
+    Business,
+    /// `P`: a private person
+    Private,
+}
+
+/// How the eBill recipient is identified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recipient {
+    Email(String),
+    /// The 17 digit E-bill recipient ID of the debtor
+    EBillId(String),
+    /// The business identification number of the debtor
+    Uid(Uid),
+}
+
+/// An eBill alternative procedure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EBill {
+    pub kind: Kind,
+    pub recipient: Recipient,
+    /// Reference ID of the bill in eBill
+    pub reference_id: Option<String>,
+}
+
+impl EBill {
+    /// An eBill for a business, identified by email, E-bill ID or UID.
+    pub fn business(recipient: &str) -> Result<Self, Error> {
+        Ok(Self { kind: Kind::Business, recipient: recipient.parse()?, reference_id: None })
+    }
+
+    /// An eBill for a private person, identified by email or E-bill ID.
+    pub fn private(recipient: &str) -> Result<Self, Error> {
+        Ok(Self { kind: Kind::Private, recipient: recipient.parse()?, reference_id: None })
+    }
+
+    pub fn with_reference_id(mut self, reference_id: &str) -> Result<Self, Error> {
+        check_reference_id(reference_id)?;
+        self.reference_id = Some(reference_id.to_string());
+        Ok(self)
+    }
+
+    /// The text for `QRBillOptions::alternative_processes`
+    pub fn to_alternative_procedure(&self) -> Result<String, Error> {
+        let text = self.to_string();
+        match text.chars().count() {
+            n if n > MAX_ALTERNATIVE_PROCEDURE_CHARS => Err(Error::TooLong(n)),
+            _ => Ok(text),
+        }
+    }
+
+    /// Find the eBill procedures among the lines of a decoded QR code payload.
+    /// Lines which do not look like eBill or are malformed are skipped.
+    pub fn find_in_payload(payload: &str) -> Vec<EBill> {
+        payload
+            .lines()
+            .filter(|line| line.starts_with("eBill/"))
+            .filter_map(|line| line.trim_end().parse().ok())
+            .collect()
+    }
+}
+
+impl std::str::FromStr for EBill {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = text.split('/');
+        if parts.next() != Some(PREFIX) {
+            return Err(Error::InvalidPrefix);
+        }
+        let kind = match parts.next().unwrap_or_default() {
+            "B" => Kind::Business,
+            "P" => Kind::Private,
+            other => return Err(Error::InvalidKind(other.to_string())),
+        };
+        let recipient = parts.next().unwrap_or_default().parse()?;
+        let reference_id = parts.next().map(|id| check_reference_id(id).map(|_| id.to_string())).transpose()?;
+        if let Some(rest) = parts.next() {
+            return Err(Error::InvalidReferenceId(format!("{}/{rest}", reference_id.unwrap_or_default())));
+        }
+        Ok(Self { kind, recipient, reference_id })
+    }
+}
+
+impl std::fmt::Display for EBill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            Kind::Business => "B",
+            Kind::Private => "P",
+        };
+        write!(f, "{PREFIX}/{kind}/{}", self.recipient)?;
+        if let Some(reference_id) = &self.reference_id {
+            write!(f, "/{reference_id}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Recipient {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidRecipient(text.to_string());
+        if text.len() == 17 && text.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(Recipient::EBillId(text.to_string()));
+        }
+        if text.starts_with("CHE") && !text.contains('@') {
+            return text.parse().map(Recipient::Uid).map_err(|_| invalid());
+        }
+        match text.split_once('@') {
+            Some((local, domain))
+                if !local.is_empty()
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && !domain.contains('@')
+                    && !text.contains(|c: char| c == '/' || c.is_whitespace() || c.is_control()) =>
+                Ok(Recipient::Email(text.to_string())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for Recipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recipient::Email(email) => write!(f, "{email}"),
+            Recipient::EBillId(id) => write!(f, "{id}"),
+            Recipient::Uid(uid) => write!(f, "CHE{}", uid.compact()),
+        }
+    }
+}
+
+fn check_reference_id(reference_id: &str) -> Result<(), Error> {
+    match reference_id.len() {
+        1..=35 if reference_id.chars().all(|c| c.is_ascii_alphanumeric()) => Ok(()),
+        _ => Err(Error::InvalidReferenceId(reference_id.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case(EBill::business("peter@sample.ch").unwrap()              , "eBill/B/peter@sample.ch")]
+    #[case(EBill::private("41010560425610173").unwrap()             , "eBill/P/41010560425610173")]
+    #[case(EBill::business("CHE-109.322.551").unwrap()              , "eBill/B/CHE109322551")]
+    #[case(EBill::business("peter@sample.ch").unwrap()
+        .with_reference_id("R2024000123").unwrap()                  , "eBill/B/peter@sample.ch/R2024000123")]
+    fn build(#[case] ebill: EBill, #[case] expected: &str) {
+        assert_eq!(ebill.to_alternative_procedure().unwrap(), expected);
+        assert_eq!(expected.parse::<EBill>().unwrap(), ebill);
+    }
+
+    #[rstest]
+    #[case("eBill"                        , Error::InvalidKind("".into()))]
+    #[case("EBill/B/peter@sample.ch"      , Error::InvalidPrefix)]
+    #[case("eBill/X/peter@sample.ch"      , Error::InvalidKind("X".into()))]
+    #[case("eBill/B/"                     , Error::InvalidRecipient("".into()))]
+    #[case("eBill/B/peter"                , Error::InvalidRecipient("peter".into()))]
+    #[case("eBill/B/@sample.ch"           , Error::InvalidRecipient("@sample.ch".into()))]
+    #[case("eBill/B/4101056042561017"     , Error::InvalidRecipient("4101056042561017".into()))]
+    #[case("eBill/B/CHE-109.322.552"      , Error::InvalidRecipient("CHE-109.322.552".into()))]
+    #[case("eBill/B/peter@sample.ch/R-1"  , Error::InvalidReferenceId("R-1".into()))]
+    #[case("eBill/B/peter@sample.ch/R1/2" , Error::InvalidReferenceId("R1/2".into()))]
+    fn parse_rejects(#[case] text: &str, #[case] expected: Error) {
+        assert_eq!(text.parse::<EBill>().unwrap_err(), expected);
+    }
+
+    #[test]
+    fn too_long() {
+        let ebill = |local_part_chars| EBill::business(&format!("{}@sample.ch", "a".repeat(local_part_chars))).unwrap();
+        assert_eq!(ebill(82).to_alternative_procedure().map(|text| text.len()), Ok(100));
+        assert_eq!(ebill(83).to_alternative_procedure(), Err(Error::TooLong(101)));
+    }
+
+    #[test]
+    fn find_in_payload() {
+        let payload = "SPC\n0200\n1\nCH4431999123000889012\n…\nEPD\n//S1/10/1\neBill/B/peter@sample.ch\nOther/Procedure";
+        assert_eq!(EBill::find_in_payload(payload), vec![EBill::business("peter@sample.ch").unwrap()]);
+    }
+
+    #[test]
+    fn alternative_procedure_of_bill() {
+        let ebill = EBill::business("peter@sample.ch").unwrap().to_alternative_procedure().unwrap();
+        let bill = crate::QRBill::new(crate::tests::options("Peter".into(), vec![ebill.clone()])).unwrap();
+        assert_eq!(EBill::find_in_payload(&bill.qr_data()), vec![ebill.parse().unwrap()]);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn parse_never_panics(input in "\\PC*") {
+            let _ = format!("eBill/{input}").parse::<EBill>();
+        }
+    }
+}
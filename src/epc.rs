@@ -0,0 +1,339 @@
+//! The EPC QR code (EPC069-12, also known as GiroCode) for SEPA credit
+//! transfers, to offer payers in the euro area a code their banking apps know.
+
+use iban::IbanLike;
+
+use crate::{
+    iso11649::{self, Iso11649},
+    Address, Currency, Iban, Language, QRBill, QRBillOptions, QrCode, Reference,
+    IBAN_ALLOWED_COUNTRIES,
+};
+
+const SERVICE_TAG: &str = "BCD";
+const VERSION: &str = "002";
+/// Character set 1 is UTF-8
+const CODING: &str = "1";
+const IDENTIFICATION: &str = "SCT";
+const MAX_NAME_CHARS: usize = 70;
+const MAX_TEXT_CHARS: usize = 140;
+const MAX_INFORMATION_CHARS: usize = 70;
+const MAX_PAYLOAD_BYTES: usize = 331;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("Only bills in EUR can be paid by SEPA credit transfer.")]
+    NotEuro,
+    #[error("QR references cannot be given in a SEPA credit transfer.")]
+    QrReference,
+    #[error("An EPC QR code must start with BCD, version 001 or 002 and SCT.")]
+    InvalidHeader,
+    #[error("Line {0} of the EPC QR code is invalid.")]
+    InvalidLine(usize),
+    #[error("The creditor reference is invalid: {0}")]
+    InvalidReference(iso11649::Error),
+    #[error("An EPC QR code may have either a creditor reference or a text, not both.")]
+    ReferenceAndText,
+    #[error("An EPC QR code may have at most 331 bytes, but this one has {0}.")]
+    TooLong(usize),
+    #[error("Only accounts from CH or LI can receive payments with a QR-bill.")]
+    UnsupportedAccount,
+    #[error("With a creditor reference, the additional information is shown to the payer, where at most 70 characters fit, but it has {0}.")]
+    InformationTooLong(usize),
+    #[error("A SEPA credit transfer needs an amount of at least 0.01, which a notification does not have.")]
+    InvalidAmount,
+}
+
+/// Where the EPC QR code is printed on full-page output: the top left corner
+/// of the code, without its quiet zone, in mm from the top left corner of the
+/// page. The code is as large as the Swiss QR Code, 46 x 46 mm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Position {
+    /// Aligned with the Swiss QR Code, 10 mm above the separation line. This
+    /// is in the letter above the bill, which must leave room for the code.
+    pub const ABOVE_PAYMENT_PART: Position = Position { x: 62.0 + 5.0, y: 297.0 - 105.0 - 10.0 - 46.0 };
+}
+
+/// The content of an EPC QR code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Epc {
+    pub bic: Option<String>,
+    pub name: String,
+    pub iban: Iban,
+    /// Amount in EUR
+    pub amount: Option<f64>,
+    /// A four letter purpose code, e.g. `GDDS`
+    pub purpose: Option<String>,
+    pub reference: Option<Iso11649>,
+    /// Unstructured remittance information, only without `reference`
+    pub text: Option<String>,
+    /// Information shown to the payer
+    pub information: Option<String>,
+}
+
+impl TryFrom<&QRBill> for Epc {
+    type Error = Error;
+
+    /// The payment of a bill in EUR as SEPA credit transfer.
+    ///
+    /// As EPC codes take either a creditor reference or a text, the additional
+    /// information of a bill with an SCOR reference is shown to the payer
+    /// instead. Rather than being cut short, additional information longer
+    /// than the 70 characters available there is rejected.
+    fn try_from(bill: &QRBill) -> Result<Self, Self::Error> {
+        if !matches!(bill.currency, Currency::Euro) {
+            return Err(Error::NotEuro);
+        }
+        if bill.amount.is_some_and(|amount| amount < 0.01) {
+            return Err(Error::InvalidAmount);
+        }
+        let message = bill.extra_infos.clone().filter(|m| !m.is_empty());
+        let (reference, text, information) = match &bill.reference {
+            Reference::Qrr(_) => return Err(Error::QrReference),
+            Reference::Scor(reference) => match message.as_ref().map(|m| m.chars().count()) {
+                Some(chars) if chars > MAX_INFORMATION_CHARS => return Err(Error::InformationTooLong(chars)),
+                _ => (Some(reference.clone()), None, message),
+            },
+            Reference::None => (None, message, None),
+        };
+        let epc = Epc {
+            bic: None,
+            name: bill.creditor.name().chars().take(MAX_NAME_CHARS).collect(),
+            iban: bill.account,
+            amount: bill.amount,
+            purpose: None,
+            reference,
+            text,
+            information,
+        };
+        epc.check_length()?;
+        Ok(epc)
+    }
+}
+
+impl Epc {
+    /// The QR code to print, with error correction level M
+    pub fn qr_code(&self) -> Result<QrCode, crate::Error> {
+        Ok(QrCode::with_error_correction_level(self.to_string(), qrcode::EcLevel::M)?)
+    }
+
+    /// Options for a QR-bill requesting the same payment, for an account in
+    /// CH or LI. The EPC code only names the creditor, so their address must
+    /// be given.
+    pub fn to_options(&self, creditor: Address, language: Language) -> Result<QRBillOptions, Error> {
+        if !IBAN_ALLOWED_COUNTRIES.contains(&self.iban.country_code()) {
+            return Err(Error::UnsupportedAccount);
+        }
+        Ok(QRBillOptions {
+            amount: self.amount,
+            currency: Currency::Euro,
+            reference: self.reference.clone().map_or(Reference::None, Reference::Scor),
+            extra_infos: self.text.clone().or_else(|| self.information.clone()),
+            language,
+            ..QRBillOptions::new(self.iban, creditor)
+        })
+    }
+
+    fn check_length(&self) -> Result<(), Error> {
+        match self.to_string().len() {
+            n if n > MAX_PAYLOAD_BYTES => Err(Error::TooLong(n)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Epc {
+    /// The payload of the EPC QR code, without trailing empty lines
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![
+            SERVICE_TAG.to_string(),
+            VERSION.to_string(),
+            CODING.to_string(),
+            IDENTIFICATION.to_string(),
+            self.bic.clone().unwrap_or_default(),
+            self.name.clone(),
+            self.iban.electronic_str().to_string(),
+            self.amount.map(|a| format!("EUR{a:.2}")).unwrap_or_default(),
+            self.purpose.clone().unwrap_or_default(),
+            self.reference.as_ref().map(|r| r.with_checksum()).unwrap_or_default(),
+            self.text.clone().unwrap_or_default(),
+            self.information.clone().unwrap_or_default(),
+        ];
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl std::str::FromStr for Epc {
+    type Err = Error;
+
+    /// Parse a decoded EPC QR code, version 001 or 002.
+    fn from_str(payload: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = payload.lines().map(str::trim).collect();
+        let line = |n: usize| lines.get(n - 1).copied().filter(|l| !l.is_empty());
+        let limited = |n: usize, max: usize| match line(n) {
+            Some(l) if l.chars().count() > max => Err(Error::InvalidLine(n)),
+            l => Ok(l.map(str::to_string)),
+        };
+        if line(1) != Some(SERVICE_TAG)
+            || !matches!(line(2), Some("001" | "002"))
+            || line(4) != Some(IDENTIFICATION)
+        {
+            return Err(Error::InvalidHeader);
+        }
+        if lines.len() > 12 {
+            return Err(Error::InvalidLine(13));
+        }
+
+        let amount = match line(8) {
+            None => None,
+            Some(amount) => {
+                let amount: f64 = amount
+                    .strip_prefix("EUR")
+                    .and_then(|a| a.parse().ok())
+                    .filter(|a: &f64| (0.01..=999_999_999.99).contains(a))
+                    .ok_or(Error::InvalidLine(8))?;
+                Some(amount)
+            }
+        };
+        let reference = line(10).map(Iso11649::parse).transpose().map_err(Error::InvalidReference)?;
+        let text = limited(11, MAX_TEXT_CHARS)?;
+        if reference.is_some() && text.is_some() {
+            return Err(Error::ReferenceAndText);
+        }
+        Ok(Epc {
+            bic: line(5).map(str::to_string),
+            name: limited(6, MAX_NAME_CHARS)?.ok_or(Error::InvalidLine(6))?,
+            iban: line(7).and_then(|iban| iban.parse().ok()).ok_or(Error::InvalidLine(7))?,
+            amount,
+            purpose: match line(9) {
+                Some(p) if p.len() != 4 || !p.chars().all(|c| c.is_ascii_alphanumeric()) =>
+                    return Err(Error::InvalidLine(9)),
+                p => p.map(str::to_string),
+            },
+            reference,
+            text,
+            information: limited(12, MAX_INFORMATION_CHARS)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{esr::Esr, tests::{address, options}};
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    fn bill(reference: Reference, extra_infos: Option<&str>, currency: Currency) -> QRBill {
+        QRBill::new(QRBillOptions {
+            account: "CH0300700110000123456".parse().unwrap(),
+            currency,
+            reference,
+            extra_infos: extra_infos.map(str::to_string),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap()
+    }
+
+    #[rstest]
+    #[case(Reference::None, Some("Rechnung 42"),
+           "BCD\n002\n1\nSCT\n\nMax Muster\nCH0300700110000123456\nEUR1949.75\n\n\nRechnung 42")]
+    #[case(Reference::Scor(Iso11649::new("539007547034")), Some("Rechnung 42"),
+           "BCD\n002\n1\nSCT\n\nMax Muster\nCH0300700110000123456\nEUR1949.75\n\nRF18539007547034\n\nRechnung 42")]
+    #[case(Reference::Scor(Iso11649::new("539007547034")), None,
+           "BCD\n002\n1\nSCT\n\nMax Muster\nCH0300700110000123456\nEUR1949.75\n\nRF18539007547034")]
+    fn from_bill(#[case] reference: Reference, #[case] extra_infos: Option<&str>, #[case] expected: &str) {
+        let epc = Epc::try_from(&bill(reference, extra_infos, Currency::Euro)).unwrap();
+        assert_eq!(epc.to_string(), expected);
+        assert_eq!(expected.parse::<Epc>().unwrap(), epc);
+    }
+
+    #[test]
+    fn from_bill_rejects() {
+        let bill_in_chf = bill(Reference::None, None, Currency::SwissFranc);
+        assert_eq!(Epc::try_from(&bill_in_chf), Err(Error::NotEuro));
+        let qrr = Reference::Qrr(Esr::try_new("210000000003139471430009017".into()).unwrap());
+        let bill_with_qrr = QRBill::new(QRBillOptions {
+            currency: Currency::Euro,
+            reference: qrr,
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        assert_eq!(Epc::try_from(&bill_with_qrr), Err(Error::QrReference));
+        let notification = QRBill::notification(QRBillOptions {
+            currency: Currency::Euro,
+            epc_code: Some(Position::ABOVE_PAYMENT_PART),
+            ..options("Max Muster".into(), vec![])
+        });
+        assert!(matches!(notification, Err(crate::Error::Epc(Error::InvalidAmount))));
+    }
+
+    #[test]
+    fn from_bill_rejects_long_information_with_reference() {
+        let scor = Reference::Scor(Iso11649::new("539007547034"));
+        let fits = bill(scor.clone(), Some(&"x".repeat(70)), Currency::Euro);
+        let too_long = bill(scor, Some(&"x".repeat(71)), Currency::Euro);
+        assert!(Epc::try_from(&fits).is_ok());
+        assert_eq!(Epc::try_from(&too_long), Err(Error::InformationTooLong(71)));
+    }
+
+    #[test]
+    fn to_options() {
+        let payload = "BCD\r\n001\r\n1\r\nSCT\r\nPOFICHBEXXX\r\nMax Muster\r\nCH1809000000800004703\r\nEUR12.5\r\nGDDS\r\nRF18 5390 0754 7034";
+        let epc: Epc = payload.parse().unwrap();
+        assert_eq!(epc.bic.as_deref(), Some("POFICHBEXXX"));
+        assert_eq!(epc.purpose.as_deref(), Some("GDDS"));
+        let bill = QRBill::new(epc.to_options(address("Max Muster".into()), Language::German).unwrap()).unwrap();
+        assert_eq!(bill.amount, Some(12.5));
+        assert_eq!(bill.reference.to_string(), "RF18 5390 0754 7034");
+
+        let german: Epc = "BCD\n002\n1\nSCT\n\nMax Muster\nDE89370400440532013000".parse().unwrap();
+        assert!(matches!(
+            german.to_options(address("Max Muster".into()), Language::German),
+            Err(Error::UnsupportedAccount),
+        ));
+    }
+
+    #[rstest]
+    #[case("BCD\n003\n1\nSCT\n\nMax\nCH5800791123000889012"                     , Error::InvalidHeader)]
+    #[case("BCD\n002\n1\nINST\n\nMax\nCH5800791123000889012"                    , Error::InvalidHeader)]
+    #[case("BCD\n002\n1\nSCT\n\n\nCH5800791123000889012"                        , Error::InvalidLine(6))]
+    #[case("BCD\n002\n1\nSCT\n\nMax\nCH5800791123000889013"                     , Error::InvalidLine(7))]
+    #[case("BCD\n002\n1\nSCT\n\nMax\nCH5800791123000889012\nCHF1"               , Error::InvalidLine(8))]
+    #[case("BCD\n002\n1\nSCT\n\nMax\nCH5800791123000889012\nEUR0"               , Error::InvalidLine(8))]
+    #[case("BCD\n002\n1\nSCT\n\nMax\nCH5800791123000889012\n\nGOODS"            , Error::InvalidLine(9))]
+    #[case("BCD\n002\n1\nSCT\n\nMax\nCH5800791123000889012\n\n\nRF18539007547034\nText", Error::ReferenceAndText)]
+    fn parse_rejects(#[case] payload: &str, #[case] expected: Error) {
+        assert_eq!(payload.parse::<Epc>().unwrap_err(), expected);
+    }
+
+    #[rstest]
+    #[case(Position::ABOVE_PAYMENT_PART)]
+    #[case(Position { x: 150.0, y: 20.0 })]
+    fn rendered_at_position(#[case] position: Position) {
+        let bill = QRBill::new(QRBillOptions {
+            currency: Currency::Euro,
+            epc_code: Some(position),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let svg = bill.create_svg(true).unwrap();
+        let translation = format!("translate({}, {})", crate::mm(position.x), crate::mm(position.y));
+        assert!(svg.contains(&translation), "{translation} is missing in\n{svg}");
+        assert!(!bill.create_svg(false).unwrap().contains("epc-code"));
+
+        let bill_in_chf = QRBill::new(QRBillOptions { epc_code: Some(position), ..options("Max Muster".into(), vec![]) });
+        assert!(matches!(bill_in_chf, Err(crate::Error::Epc(Error::NotEuro))));
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn parse_never_panics(input in "\\PC*") {
+            let _ = format!("BCD\n002\n1\nSCT\n{input}").parse::<Epc>();
+        }
+    }
+}
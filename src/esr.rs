@@ -1,54 +1,107 @@
-#[derive(Debug, Clone)]
+//! QR references (QRR): 27 digits, the last of which is a modulo 10
+//! recursive check digit over the other 26.
+
+/// A validated QR reference
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Esr {
+    /// Exactly 27 ASCII digits, including the check digit
     number: String,
 }
 
-#[derive(thiserror::Error, Debug)]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
 pub enum Error {
-    #[error("Length must be between 5 and 25.")]
-    InvalidLength,
-    #[error("Number must start with 'RF'.")]
-    InvalidFormat,
-    #[error("Checksum is invalid.")]
-    InvalidChecksum,
+    #[error("A QR reference must have 27 digits, but {0} were given.")]
+    InvalidLength(usize),
+    #[error("A QR reference may only contain digits, but '{0}' was given.")]
+    InvalidCharacter(char),
+    #[error("The check digit should be {expected}, but is {found}.")]
+    InvalidChecksum { expected: u8, found: u8 },
+    #[error("The {part} has {length} digits, but at most {max} fit into a QR reference.")]
+    PartTooLong { part: &'static str, length: usize, max: usize },
 }
 
+/// Number of digits in a QR reference, including its check digit
+const QRR_DIGITS: usize = 27;
+const BESR_ID_MAX_DIGITS: usize = 6;
+const INVOICE_NUMBER_DIGITS: usize = 10;
+
 impl Esr {
+    /// Validate a QR reference given with or without the spaces it is
+    /// printed with.
     pub fn try_new(number: String) -> Result<Self, Error> {
-        let number = number.replace(' ', "").trim_start_matches('0').to_string();
-        if number.len() > 27 {
-            return Err(Error::InvalidLength);
-        }
-        if !number.chars().all(char::is_numeric) {
-            return Err(Error::InvalidFormat);
-        }
+        number.parse()
+    }
 
-        if number[number.len() - 1..number.len()]
-            != checksum((number[..number.len() - 1]).to_string())
-        {
-            return Err(Error::InvalidChecksum);
+    /// Build a QR reference from the numbers an ERP allocates it from.
+    ///
+    /// The 26 digits before the check digit are laid out as
+    ///
+    /// * the BESR-ID assigned by the bank, if any, of at most 6 digits,
+    /// * the customer number, padded with leading zeros to fill the digits
+    ///   not used by the other two parts,
+    /// * the invoice number, padded with leading zeros to 10 digits.
+    ///
+    /// The check digit is calculated with the modulo 10 recursive algorithm.
+    pub fn generate(
+        besr_id: Option<&str>,
+        customer_number: &str,
+        invoice_number: &str,
+    ) -> Result<Self, Error> {
+        let besr_id = besr_id.unwrap_or_default();
+        let customer_width = QRR_DIGITS - 1 - BESR_ID_MAX_DIGITS.min(besr_id.len()) - INVOICE_NUMBER_DIGITS;
+        for (part, digits, max) in [
+            ("BESR-ID"        , besr_id        , BESR_ID_MAX_DIGITS   ),
+            ("customer number", customer_number, customer_width       ),
+            ("invoice number" , invoice_number , INVOICE_NUMBER_DIGITS),
+        ] {
+            if let Some(c) = digits.chars().find(|c| !c.is_ascii_digit()) {
+                return Err(Error::InvalidCharacter(c));
+            }
+            if digits.len() > max {
+                return Err(Error::PartTooLong { part, length: digits.len(), max });
+            }
         }
-        Ok(Self { number })
+        let number = format!("{besr_id}{customer_number:0>customer_width$}{invoice_number:0>INVOICE_NUMBER_DIGITS$}");
+        let check_digit = checksum(&number);
+        Ok(Self { number: format!("{number}{check_digit}") })
     }
 
+    /// The 27 digits of the reference, as encoded in the QR code
     pub fn to_raw(&self) -> String {
         self.number.clone()
     }
 }
 
-fn checksum(number: String) -> String {
-    let digits = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
-    let mut c = 0usize;
-    for n in number.chars() {
-        c = digits[(n.to_digit(10).unwrap() as usize + c) % 10];
+impl std::str::FromStr for Esr {
+    type Err = Error;
+
+    fn from_str(number: &str) -> Result<Self, Self::Err> {
+        let number: String = number.chars().filter(|c| !c.is_whitespace()).collect();
+        if let Some(c) = number.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(Error::InvalidCharacter(c));
+        }
+        // All ASCII from here on: byte lengths and indices are character ones
+        if number.len() != QRR_DIGITS {
+            return Err(Error::InvalidLength(number.len()));
+        }
+        let (digits, check_digit) = number.split_at(QRR_DIGITS - 1);
+        let expected = checksum(digits);
+        let found = check_digit.as_bytes()[0] - b'0';
+        if expected != found {
+            return Err(Error::InvalidChecksum { expected, found });
+        }
+        Ok(Self { number })
     }
-    ((10 - c) % 10).to_string()
+}
+
+/// Modulo 10 recursive check digit of `digits`, which must all be ASCII digits
+fn checksum(digits: &str) -> u8 {
+    crate::checkdigit::mod10_recursive(digits).expect("Only called with digits")
 }
 
 impl std::fmt::Display for Esr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let number = "0".repeat(27) + &self.number;
-        let number = &number[number.len() - 27..];
+        let number = &self.number;
         write!(f, "{}",
                number[..2].to_string()
                + " "
@@ -62,3 +115,86 @@ impl std::fmt::Display for Esr {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case(None          , "1234"  , "56789"     , "00 00000 00000 12340 00005 67893")]
+    #[case(Some("210000"), "31394" , "7143000901", "21 00000 00003 13947 14300 09017")] // style guide example
+    #[case(Some("123456"), "1"     , "1"         , "12 34560 00000 00010 00000 00017")]
+    fn generate(
+        #[case] besr_id: Option<&str>,
+        #[case] customer_number: &str,
+        #[case] invoice_number: &str,
+        #[case] expected: &str,
+    ) {
+        let esr = Esr::generate(besr_id, customer_number, invoice_number).unwrap();
+        assert_eq!(esr.to_string(), expected);
+        // The generated reference passes validation by `try_new`
+        assert_eq!(Esr::try_new(expected.to_string()).unwrap(), esr);
+    }
+
+    #[rstest]
+    #[case(Some("1234567"), "1"          , "1"          )]
+    #[case(Some("123456") , "12345678901", "1"          )]
+    #[case(None           , "1"          , "12345678901")]
+    #[case(None           , "12a"        , "1"          )]
+    #[case(Some("12 34")  , "1"          , "1"          )]
+    fn generate_rejects(
+        #[case] besr_id: Option<&str>,
+        #[case] customer_number: &str,
+        #[case] invoice_number: &str,
+    ) {
+        assert!(Esr::generate(besr_id, customer_number, invoice_number).is_err());
+    }
+
+    #[rstest]
+    #[case("210000000003139471430009017")]
+    #[case("21 00000 00003 13947 14300 09017")]
+    #[case("\t21 00000 00003 13947 14300 09017\n")]
+    #[case("000000000000000000000000000")]
+    fn parse(#[case] input: &str) {
+        let esr: Esr = input.parse().unwrap();
+        assert_eq!(esr.to_raw().len(), 27);
+    }
+
+    #[rstest]
+    #[case(""                                , Error::InvalidLength(0))]
+    #[case("0"                               , Error::InvalidLength(1))]
+    #[case("   "                             , Error::InvalidLength(0))]
+    #[case("21000000000313947143000901"      , Error::InvalidLength(26))]
+    #[case("0210000000003139471430009017"    , Error::InvalidLength(28))]
+    #[case("21 00000 00003 13947 14300 0901x", Error::InvalidCharacter('x'))]
+    #[case("RF18539007547034"                , Error::InvalidCharacter('R'))]
+    #[case("21000000000313947143000901٣"     , Error::InvalidCharacter('٣'))]
+    #[case("210000000003139471430009018"     , Error::InvalidChecksum { expected: 7, found: 8 })]
+    fn parse_rejects(#[case] input: &str, #[case] expected: Error) {
+        assert_eq!(input.parse::<Esr>().unwrap_err(), expected);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn parse_never_panics(input in "\\PC*") {
+            let _ = input.parse::<Esr>();
+        }
+
+        #[test]
+        fn parse_never_panics_on_digits_and_spaces(input in "[0-9 ]{0,40}") {
+            let _ = input.parse::<Esr>();
+        }
+
+        #[test]
+        fn generated_references_parse(
+            besr_id  in proptest::option::of("[0-9]{0,6}"),
+            customer in "[0-9]{0,10}",
+            invoice  in "[0-9]{0,10}",
+        ) {
+            let esr = Esr::generate(besr_id.as_deref(), &customer, &invoice).unwrap();
+            proptest::prop_assert_eq!(esr.to_string().parse::<Esr>().unwrap(), esr);
+        }
+    }
+}
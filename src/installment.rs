@@ -0,0 +1,315 @@
+//! Installment plans: a total split between several bills, each due on its
+//! own date and with its own reference, so every payment can be reconciled
+//! with the installment it pays.
+
+use chrono::NaiveDate;
+
+use crate::{
+    esr::{self, Esr},
+    iso11649::Iso11649,
+    swico::S1,
+    QRBill, QRBillOptions, Reference,
+};
+
+/// Installments are numbered with two digits in their references
+const MAX_INSTALLMENTS: usize = 99;
+/// Leads the invoice number field of installment QR references, which regular
+/// invoice numbers of up to 9 digits leave zero
+const QRR_INSTALLMENT_MARK: char = '9';
+/// Digits of the invoice number field of a QR reference left for the invoice
+/// number, after the mark and the installment number
+const MAX_QRR_INVOICE_DIGITS: usize = 10 - 1 - 2;
+/// Characters of a creditor reference, without `RF` and check digits, which
+/// are left for the base reference after the installment number
+const MAX_SCOR_BASE_CHARS: usize = 21 - 2;
+const MAX_TOTAL: f64 = 999_999_999.99;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("An installment plan needs at least one due date.")]
+    NoInstallments,
+    #[error("An installment plan can have at most 99 installments, but {0} were given.")]
+    TooManyInstallments(usize),
+    #[error("The due dates must be in ascending order, but {1} follows {0}.")]
+    UnorderedDueDates(NaiveDate, NaiveDate),
+    #[error("The total must be between 0.01 and 999999999.99, but {0:.2} was given.")]
+    InvalidTotal(f64),
+    #[error("A total of {total:.2} cannot be split into {installments} installments of at least 0.01.")]
+    TotalTooSmall { total: f64, installments: usize },
+    #[error("The base reference '{0}' is too long to append the installment number to.")]
+    ReferenceTooLong(String),
+    #[error("A base reference may only contain letters and digits, or only digits for QR references, but '{0}' was given.")]
+    InvalidCharacter(char),
+    #[error("No QR reference can be made for the installments: {0}")]
+    QrReference(esr::Error),
+}
+
+/// The reference each installment's reference is derived from by appending
+/// the number of the installment as two digits: 01, 02, …
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BaseReference {
+    /// QR references built by `Esr::generate`. Their invoice number field is
+    /// a 9, the invoice number padded to 7 digits and the installment number,
+    /// so they never equal the reference of a regular invoice whose number
+    /// has at most 9 digits. The invoice number may have at most 7 digits.
+    Qrr {
+        besr_id: Option<String>,
+        customer_number: String,
+        invoice_number: String,
+    },
+    /// Creditor references of the given text followed by the installment
+    /// number. The text may have at most 19 letters and digits.
+    Scor(String),
+}
+
+impl BaseReference {
+    /// The reference of the installment `number`, counting from 1
+    pub fn installment(&self, number: usize) -> Result<Reference, crate::Error> {
+        match self {
+            BaseReference::Qrr { besr_id, customer_number, invoice_number } => {
+                if let Some(c) = invoice_number.chars().find(|c| !c.is_ascii_digit()) {
+                    return Err(crate::Error::Installment(Error::InvalidCharacter(c)));
+                }
+                if invoice_number.len() > MAX_QRR_INVOICE_DIGITS {
+                    return Err(crate::Error::Installment(Error::ReferenceTooLong(invoice_number.clone())));
+                }
+                Esr::generate(
+                    besr_id.as_deref(),
+                    customer_number,
+                    &format!("{QRR_INSTALLMENT_MARK}{invoice_number:0>MAX_QRR_INVOICE_DIGITS$}{number:02}"),
+                )
+                .map(Reference::Qrr)
+                .map_err(|e| crate::Error::Installment(Error::QrReference(e)))
+            }
+            BaseReference::Scor(base) => {
+                let compact: String = base.chars().filter(|c| !c.is_whitespace()).collect();
+                if compact.chars().count() > MAX_SCOR_BASE_CHARS {
+                    return Err(crate::Error::Installment(Error::ReferenceTooLong(base.clone())));
+                }
+                if let Some(c) = compact.chars().find(|c| !c.is_ascii_alphanumeric()) {
+                    return Err(crate::Error::Installment(Error::InvalidCharacter(c)));
+                }
+                Ok(Reference::Scor(Iso11649::new(&format!("{compact}{number:02}"))))
+            }
+        }
+    }
+}
+
+/// The bills of an installment plan, in the order they are due
+pub struct Plan {
+    pub bills: Vec<QRBill>,
+}
+
+impl Plan {
+    /// Split `total` between one bill per date of `schedule`.
+    ///
+    /// Every bill is made from `template`, with its due date from `schedule`
+    /// and its reference derived from `reference`. The total is split into
+    /// equal amounts, rounded down to the centime; what is left over is added
+    /// to the last installment.
+    ///
+    /// Bill information of `template` must have a document date (`/11/`): the
+    /// due date of every installment is encoded as its net payment term,
+    /// counted from that date. The payment conditions (`/40/`) of `template`
+    /// are replaced by it, as they would only fit one of the due dates. VAT
+    /// details (`/32/`) must add up to `total`, not to the installments.
+    pub fn new(
+        template: &QRBillOptions,
+        total: f64,
+        schedule: &[NaiveDate],
+        reference: &BaseReference,
+    ) -> Result<Self, crate::Error> {
+        if let Some(vat_details) = template.bill_information.as_ref().and_then(|s1| s1.vat_details.as_ref()) {
+            vat_details.validate(Some(total)).map_err(crate::Error::BillInformation)?;
+        }
+        let amounts = split(total, schedule).map_err(crate::Error::Installment)?;
+        let bills = schedule
+            .iter()
+            .zip(amounts)
+            .enumerate()
+            .map(|(i, (due_date, amount))| {
+                QRBill::create(QRBillOptions {
+                    amount: Some(amount),
+                    due_date: Some(*due_date),
+                    reference: reference.installment(i + 1)?,
+                    bill_information: template.bill_information.clone().map(|s1| S1 { conditions: None, ..s1 }),
+                    ..template.clone()
+                }, false)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { bills })
+    }
+
+    /// Returns a PDF with every bill of the plan on a page of its own.
+    pub fn create_pdf(&self) -> Result<Vec<u8>, crate::Error> {
+        QRBill::create_pdf(&self.bills, true)
+    }
+
+    /// Writes every bill of the plan on a page of its own into a PDF file.
+    pub fn write_pdf_to_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), crate::Error> {
+        std::fs::write(path, self.create_pdf()?)?;
+        Ok(())
+    }
+}
+
+/// The amount of every installment
+fn split(total: f64, schedule: &[NaiveDate]) -> Result<Vec<f64>, Error> {
+    match schedule.len() {
+        0 => return Err(Error::NoInstallments),
+        n if n > MAX_INSTALLMENTS => return Err(Error::TooManyInstallments(n)),
+        _ => {}
+    }
+    if let Some(dates) = schedule.windows(2).find(|dates| dates[0] >= dates[1]) {
+        return Err(Error::UnorderedDueDates(dates[0], dates[1]));
+    }
+    if !(total.is_finite() && (0.01..=MAX_TOTAL).contains(&total)) {
+        return Err(Error::InvalidTotal(total));
+    }
+    let installments = schedule.len() as i64;
+    let total_centimes = (total * 100.0).round() as i64;
+    let centimes = total_centimes / installments;
+    if centimes == 0 {
+        return Err(Error::TotalTooSmall { total, installments: schedule.len() });
+    }
+    let last = total_centimes - centimes * (installments - 1);
+    Ok((1..installments)
+        .map(|_| centimes)
+        .chain([last])
+        .map(|c| c as f64 / 100.0)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::options;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    fn dates(days: &[u32]) -> Vec<NaiveDate> {
+        days.iter().map(|day| NaiveDate::from_ymd_opt(2024, 5, *day).unwrap()).collect()
+    }
+
+    #[rstest]
+    #[case(1000.00 , &[1, 2, 3]   , vec![333.33, 333.33, 333.34])]
+    #[case(  90.00 , &[1, 2, 3]   , vec![ 30.00,  30.00,  30.00])]
+    #[case(  10.00 , &[1]         , vec![ 10.00                ])]
+    #[case(   0.05 , &[1, 2, 3, 4], vec![  0.01,   0.01,   0.01,   0.02])]
+    fn splits(#[case] total: f64, #[case] days: &[u32], #[case] expected: Vec<f64>) {
+        let amounts = split(total, &dates(days)).unwrap();
+        assert_eq!(amounts, expected);
+        assert_eq!(format!("{:.2}", amounts.iter().sum::<f64>()), format!("{total:.2}"));
+    }
+
+    #[rstest]
+    #[case(100.0   , &[]        , Error::NoInstallments)]
+    #[case(100.0   , &[2, 1]    , Error::UnorderedDueDates(dates(&[2])[0], dates(&[1])[0]))]
+    #[case(100.0   , &[1, 1]    , Error::UnorderedDueDates(dates(&[1])[0], dates(&[1])[0]))]
+    #[case(  0.0   , &[1]       , Error::InvalidTotal(0.0))]
+    #[case(f64::NAN, &[1]       , Error::InvalidTotal(f64::NAN))]
+    #[case(  0.02  , &[1, 2, 3] , Error::TotalTooSmall { total: 0.02, installments: 3 })]
+    fn split_rejects(#[case] total: f64, #[case] days: &[u32], #[case] expected: Error) {
+        // NaN is never equal to itself, so compare the messages.
+        assert_eq!(split(total, &dates(days)).unwrap_err().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn too_many_installments() {
+        let schedule: Vec<_> = (0..100).map(|i| dates(&[1])[0] + chrono::Days::new(i)).collect();
+        assert_eq!(split(1000.0, &schedule), Err(Error::TooManyInstallments(100)));
+    }
+
+    #[rstest]
+    #[case(BaseReference::Qrr { besr_id: None, customer_number: "123".into(), invoice_number: "4567".into() },
+           &["000000000000012390004567014", "000000000000012390004567022", "000000000000012390004567038"])]
+    #[case(BaseReference::Scor("INV 4567".into()),
+           &["RF03INV456701", "RF73INV456702", "RF46INV456703"])]
+    fn plan(#[case] reference: BaseReference, #[case] expected_references: &[&str]) {
+        let plan = Plan::new(&options("Max Muster".into(), vec![]), 1000.0, &dates(&[1, 15, 29]), &reference).unwrap();
+        let lines: Vec<Vec<String>> = plan.bills.iter().map(|bill| bill.qr_data().lines().map(str::to_string).collect()).collect();
+        assert_eq!(lines.iter().map(|l| l[18].as_str()).collect::<Vec<_>>(), ["333.33", "333.33", "333.34"]);
+        assert_eq!(lines.iter().map(|l| l[28].as_str()).collect::<Vec<_>>(), expected_references);
+        assert_eq!(plan.bills.iter().map(|bill| bill.due_date).collect::<Vec<_>>(), dates(&[1, 15, 29]).into_iter().map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn plan_rejects_long_references() {
+        let qrr = BaseReference::Qrr { besr_id: None, customer_number: "1".into(), invoice_number: "12345678".into() };
+        let scor = BaseReference::Scor("A".repeat(20));
+        for reference in [qrr, scor] {
+            assert!(Plan::new(&options("Max Muster".into(), vec![]), 100.0, &dates(&[1]), &reference).is_err());
+        }
+    }
+
+    #[rstest]
+    #[case(BaseReference::Qrr { besr_id: None, customer_number: "1".into(), invoice_number: "12345678".into() },
+           Error::ReferenceTooLong("12345678".into()))]
+    #[case(BaseReference::Qrr { besr_id: None, customer_number: "1".into(), invoice_number: "45-1".into() },
+           Error::InvalidCharacter('-'))]
+    #[case(BaseReference::Qrr { besr_id: None, customer_number: "1".repeat(17), invoice_number: "45".into() },
+           Error::QrReference(esr::Error::PartTooLong { part: "customer number", length: 17, max: 16 }))]
+    #[case(BaseReference::Scor("A".repeat(20)), Error::ReferenceTooLong("A".repeat(20)))]
+    #[case(BaseReference::Scor("INV-45".into()), Error::InvalidCharacter('-'))]
+    fn installment_rejects(#[case] reference: BaseReference, #[case] expected: Error) {
+        assert!(matches!(reference.installment(1), Err(crate::Error::Installment(error)) if error == expected));
+    }
+
+    #[test]
+    fn qrr_installments_differ_from_regular_invoices() {
+        let installment = BaseReference::Qrr { besr_id: None, customer_number: "123".into(), invoice_number: "45".into() };
+        let regular = Esr::generate(None, "123", "4501").unwrap();
+        assert_ne!(installment.installment(1).unwrap().to_string(), Reference::Qrr(regular).to_string());
+    }
+
+    #[test]
+    fn plan_replaces_payment_conditions() {
+        let template = QRBillOptions {
+            bill_information: Some("//S1/10/42/11/240501/40/2:10;0:30".parse().unwrap()),
+            ..options("Max Muster".into(), vec![])
+        };
+        let reference = BaseReference::Scor("4567".into());
+        let plan = Plan::new(&template, 1000.0, &dates(&[1, 15, 29]), &reference).unwrap();
+        let bill_information: Vec<_> = plan.bills.iter().map(|bill| bill.qr_data().lines().nth(31).unwrap().to_string()).collect();
+        assert_eq!(bill_information, [
+            "//S1/10/42/11/240501/40/0:0",
+            "//S1/10/42/11/240501/40/0:14",
+            "//S1/10/42/11/240501/40/0:28",
+        ]);
+    }
+
+    #[test]
+    fn plan_checks_vat_against_total() {
+        let template = QRBillOptions {
+            bill_information: Some("//S1/11/240501/32/8:1000".parse().unwrap()),
+            ..options("Max Muster".into(), vec![])
+        };
+        let reference = BaseReference::Scor("4567".into());
+        assert_eq!(Plan::new(&template, 1080.0, &dates(&[1, 15]), &reference).unwrap().bills.len(), 2);
+        assert!(matches!(
+            Plan::new(&template, 1000.0, &dates(&[1, 15]), &reference),
+            Err(crate::Error::BillInformation(crate::swico::Error::AmountMismatch { .. })),
+        ));
+    }
+
+    #[test]
+    fn plan_needs_document_date() {
+        let template = QRBillOptions {
+            bill_information: Some("//S1/10/42".parse().unwrap()),
+            ..options("Max Muster".into(), vec![])
+        };
+        let reference = BaseReference::Scor("4567".into());
+        assert!(matches!(
+            Plan::new(&template, 1000.0, &dates(&[1, 15]), &reference),
+            Err(crate::Error::BillInformation(crate::swico::Error::MissingDocumentDate)),
+        ));
+    }
+
+    #[test]
+    fn pdf_has_a_page_per_bill() {
+        let reference = BaseReference::Scor("4567".into());
+        let plan = Plan::new(&options("Max Muster".into(), vec![]), 100.0, &dates(&[1, 2, 3]), &reference).unwrap();
+        let pdf = plan.create_pdf().unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert!(String::from_utf8_lossy(&pdf).contains("/Count 3"));
+    }
+}
@@ -5,22 +5,86 @@ pub struct Iso11649 {
     original: DigitsBase36,
 }
 
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("A creditor reference must start with 'RF' followed by two check digits.")]
+    InvalidFormat,
+    #[error("A creditor reference must have between 5 and 25 characters, but {0} were given.")]
+    InvalidLength(usize),
+    #[error("A creditor reference may only contain the letters A-Z and digits, but '{0}' was given.")]
+    InvalidCharacter(char),
+    #[error("The check digits should be {expected:02}, but are {found:02}.")]
+    InvalidChecksum { expected: u8, found: u8 },
+}
+
+/// References are equal when they encode the same, whichever text they were
+/// created from
+impl PartialEq for Iso11649 {
+    fn eq(&self, other: &Self) -> bool {
+        self.with_checksum() == other.with_checksum()
+    }
+}
+
+impl Eq for Iso11649 {}
+
+/// Longest reference, without `RF` and check digits, allowed by ISO 11649
+const MAX_REFERENCE_CHARS: usize = 21;
+
 impl Iso11649 {
+    /// Create a creditor reference from any text, calculating its check
+    /// digits. Characters which cannot appear in a reference are dropped and
+    /// the text is cut to the 21 characters that fit.
     pub fn new(any_utf8_text: &str) -> Self {
         Self { original: any_utf8_text.into() }
     }
 
+    /// Verify a complete creditor reference, such as `RF18 5390 0754 7034`.
+    ///
+    /// Spaces are ignored and letters may be given in either case; apart from
+    /// that the reference must be exactly as encoded in a QR code.
+    pub fn parse(reference: &str) -> Result<Self, Error> {
+        let reference: String = reference
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        if let Some(c) = reference.chars().find(|c| !c.is_ascii_alphanumeric()) {
+            return Err(Error::InvalidCharacter(c));
+        }
+        // All ASCII from here on: byte lengths and indices are character ones
+        if !reference.starts_with("RF") || reference.len() < 4 {
+            return Err(Error::InvalidFormat);
+        }
+        let (check_digits, body) = reference[2..].split_at(2);
+        let found: u8 = check_digits.parse().map_err(|_| Error::InvalidFormat)?;
+        if body.is_empty() || body.len() > MAX_REFERENCE_CHARS {
+            return Err(Error::InvalidLength(reference.len()));
+        }
+        let parsed = Self { original: DigitsBase36(body.to_string()) };
+        let expected = parsed.check_digits();
+        if expected != found {
+            return Err(Error::InvalidChecksum { expected, found });
+        }
+        Ok(parsed)
+    }
+
     pub fn original(&self) -> String {
         self.original.0.clone()
     }
 
     pub fn with_checksum(&self) -> String {
-        let text_in_base_36 = self.without_checksum();
-        let base_36_at_most_21_digits: String = text_in_base_36.chars().take(21).collect();
-        let text_with_rf00 = DigitsBase36(format!("{base_36_at_most_21_digits}RF00"));
+        format!("RF{:02}{}", self.check_digits(), self.truncated())
+    }
+
+    /// The reference cut to the 21 characters allowed by ISO 11649
+    fn truncated(&self) -> String {
+        self.without_checksum().chars().take(MAX_REFERENCE_CHARS).collect()
+    }
+
+    fn check_digits(&self) -> u8 {
+        let text_with_rf00 = DigitsBase36(format!("{}RF00", self.truncated()));
         let digits_decimal = DigitsBase10::from(&text_with_rf00);
-        let check_digits = 98 - digits_decimal % 97;
-        format!("RF{check_digits:02}{base_36_at_most_21_digits}")
+        98 - digits_decimal % 97
     }
 
     pub fn without_checksum(&self) -> String {
@@ -64,11 +128,8 @@ impl std::ops::Rem<u8> for DigitsBase10 {
     type Output = u8;
 
     fn rem(self, rhs: u8) -> Self::Output {
-        let mut res = 0;
-        for digit in self.0.chars() {
-            res = (res * 10 + digit.to_digit(10).unwrap()) % (rhs as u32)
-        }
-        res.try_into().unwrap()
+        crate::checkdigit::remainder(&self.0, rhs as u32)
+            .expect("Decimal digits only ever contain digits, and the divisor must not be zero") as u8
     }
 }
 
@@ -177,13 +238,53 @@ mod tests {
         assert_eq!(parsed.with_checksum()   , input);
     }
 
+    #[rstest]
+    #[case("RF18539007547034"          , "RF18539007547034")]
+    #[case("RF18 5390 0754 7034"       , "RF18539007547034")]
+    #[case("rf18 5390 0754 7034"       , "RF18539007547034")]
+    #[case("RF25A"                     , "RF25A")]
+    #[case("RF16ABCDEFGHIJABCDEFGHIJA" , "RF16ABCDEFGHIJABCDEFGHIJA")]
+    fn parse(#[case] input: &str, #[case] expected: &str) {
+        let parsed = Iso11649::parse(input).unwrap();
+        assert_eq!(parsed.with_checksum(), expected);
+        let reference = crate::Reference::Scor(parsed);
+        assert_eq!(reference.data_list(), vec!["SCOR".to_string(), expected.to_string()]);
+    }
+
+    #[rstest]
+    #[case(""                          , Error::InvalidFormat)]
+    #[case("RF1"                       , Error::InvalidFormat)]
+    #[case("RF18"                      , Error::InvalidLength(4))]
+    #[case("XX18539007547034"          , Error::InvalidFormat)]
+    #[case("RFAB539007547034"          , Error::InvalidFormat)]
+    #[case("RF18-5390-0754-7034"       , Error::InvalidCharacter('-'))]
+    #[case("RF18 5390 0754 7034 é"     , Error::InvalidCharacter('é'))]
+    #[case("RF16ABCDEFGHIJABCDEFGHIJAB", Error::InvalidLength(26))]
+    #[case("RF19539007547034"          , Error::InvalidChecksum { expected: 18, found: 19 })]
+    fn parse_rejects(#[case] input: &str, #[case] expected: Error) {
+        assert_eq!(Iso11649::parse(input).unwrap_err(), expected);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn parse_never_panics(input in "\\PC*") {
+            let _ = Iso11649::parse(&input);
+        }
+
+        #[test]
+        fn created_references_parse(text in "[A-Z0-9]{1,21}") {
+            let created = Iso11649::new(&text).with_checksum();
+            proptest::prop_assert_eq!(Iso11649::parse(&created).unwrap().with_checksum(), created);
+        }
+    }
+
     struct Example { bill: crate::QRBill, expected_data: String }
 
     #[fixture]
     fn example1() -> Example {
         use crate::{Address, Currency, Language, QRBill, QRBillOptions, Reference, StructuredAddress};
 
-        let iban = "CH8200788000C33011582";
+        let iban = "CH0300700110000123456";
         let creditor_name = "Etat de Genève";
         let creditor_street = "Avenue des Impôts";
         let creditor_house_number = 42;
@@ -208,7 +309,7 @@ mod tests {
         let debtor_city = "Rochemouillé-sur-Lac";
         let debtor_country = isocountry::CountryCode::CHE;
         let extra_infos = "Extra infos";
-        // TODO due_date seems to have no effect on the data encoded in the QR code
+        // Without bill information, the due date is printed but not encoded
         let due_date = chrono::NaiveDate::from_ymd_opt(2024, 6, 30)
             .expect("Hard-wired test date should parse");
         // let alternative1 = "Alternative process 1";
@@ -236,10 +337,17 @@ mod tests {
             debtor,
             reference: Reference::Scor(reference),
             extra_infos: Some(extra_infos.into()),
+            bill_information: None,
             alternative_processes: vec![],// TODO reinstate when alt-procs implemented vec![alternative1.into(), alternative2.into()],
             language: Language::French,
+            texts: None,
+            font_size: crate::FontSize::RECOMMENDED,
             top_line: true,
+            separate_note: true,
             payment_line: true,
+            epc_code: None,
+            due_date_heading: true,
+            svg_id: None,
         }).expect("Should be able to create test example QRBill");
 
         // Write example out to local directory, for easier human inspection.
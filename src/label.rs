@@ -1,5 +1,10 @@
 //! Translations of all the QRbill heading labels into the four allowed
-//! languages.
+//! languages, and of the other texts this crate prints on or encodes into
+//! bills.
+//!
+//! The headings (`Labels`) are prescribed by the Swiss Payment Standards and
+//! cannot be changed. The other texts (`Texts`) are not, so they can be
+//! replaced, e.g. to print Romansh wording.
 
 #[derive(Debug, Clone, Copy)]
 /// The languages allowed in QRbills
@@ -22,11 +27,10 @@ pub struct Labels {
     pub acceptance_point:       &'static str,
     pub payable_by:             &'static str,
     pub payable_by_extended:    &'static str,
-    pub payable_by_date:        &'static str,
 }
 
 impl Labels {
-    /// Create set of all known labels in the given language
+    /// The official headings in the given language
     pub fn for_language(language: Language) -> Labels {
         Labels {
             payment_part:           PAYMENT_PART           .to(language),
@@ -39,7 +43,35 @@ impl Labels {
             acceptance_point:       ACCEPTANCE_POINT       .to(language),
             payable_by:             PAYABLE_BY             .to(language),
             payable_by_extended:    PAYABLE_BY_EXTENDED    .to(language),
-            payable_by_date:        PAYABLE_BY_DATE        .to(language),
+        }
+    }
+}
+
+/// The texts which are not defined by the Swiss Payment Standards, in a
+/// single language. Start from `Texts::for_language` and replace any of them
+/// to change the wording, or to print them in a language of your own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Texts {
+    /// The heading of the due date, see `QRBillOptions::due_date_heading`
+    pub payable_by_date: String,
+    /// The notice on a bill which only notifies of a payment made by other
+    /// means, see `QRBill::notification`
+    pub do_not_use_for_payment: String,
+    /// The note on reminders of the first, second and third level, see
+    /// `QRBill::reminder`
+    pub reminder: [String; 3],
+    /// The note above the top line, see `QRBillOptions::separate_note`
+    pub separate_before_paying_in: String,
+}
+
+impl Texts {
+    /// The texts of this crate in the given language
+    pub fn for_language(language: Language) -> Texts {
+        Texts {
+            payable_by_date:           PAYABLE_BY_DATE           .to(language).to_string(),
+            do_not_use_for_payment:    DO_NOT_USE_FOR_PAYMENT    .to(language).to_string(),
+            reminder:                  REMINDER.map(|note| note.to(language).to_string()),
+            separate_before_paying_in: SEPARATE_BEFORE_PAYING_IN .to(language).to_string(),
         }
     }
 }
@@ -115,8 +147,16 @@ pub const PAYABLE_BY_EXTENDED: Translation = Translation {
     it: "Pagabile da (nome/indirizzo)",
 };
 
-// TODO WHAT IS THIS? CAN'T FIND IT ANYWHERE IN THE STANDARD!
-// The extra ending space allows to differentiate from the other: "Payable by" above.
+pub const SEPARATE_BEFORE_PAYING_IN: Translation = Translation {
+    en: "Separate before paying in",
+    de: "Vor der Einzahlung abzutrennen",
+    fr: "A détacher avant le versement",
+    it: "Da staccare prima del versamento",
+};
+
+// Not in Annex D: the heading of the due date, which is only printed with
+// `QRBillOptions::due_date_heading`. The standard carries the due date in the
+// bill information instead.
 pub const PAYABLE_BY_DATE: Translation = Translation {
     en: "Payable by",
     de: "Zahlbar bis",
@@ -124,6 +164,39 @@ pub const PAYABLE_BY_DATE: Translation = Translation {
     it: "Pagabile fino al",
 };
 
+// The notice in the unstructured message of a bill which only notifies of a
+// payment made by other means, such as eBill or LSV+.
+pub const DO_NOT_USE_FOR_PAYMENT: Translation = Translation {
+    en: "DO NOT USE FOR PAYMENT",
+    de: "NICHT ZUR ZAHLUNG VERWENDEN",
+    fr: "NE PAS UTILISER POUR LE PAIEMENT",
+    it: "NON UTILIZZARE PER IL PAGAMENTO",
+};
+
+// The note in the additional information of a reminder, by its level
+pub const REMINDER: [Translation; 3] = [
+    Translation {
+        en: "Reminder 1",
+        de: "1. Mahnung",
+        fr: "1er rappel",
+        it: "1° sollecito",
+    },
+    Translation {
+        en: "Reminder 2",
+        de: "2. Mahnung",
+        fr: "2e rappel",
+        it: "2° sollecito",
+    },
+    Translation {
+        en: "Reminder 3",
+        de: "3. Mahnung",
+        fr: "3e rappel",
+        it: "3° sollecito",
+    },
+];
+
+/// A text in each of the languages allowed in QRbills
+#[derive(Debug, Clone, Copy)]
 pub struct Translation {
     en: &'static str,
     de: &'static str,
@@ -133,7 +206,7 @@ pub struct Translation {
 
 impl Translation {
 
-    fn to(&self, language: Language) -> &'static str {
+    pub fn to(&self, language: Language) -> &'static str {
         use Language::*;
         match language {
             German  => self.de,
@@ -9,17 +9,36 @@ use svg::{
 };
 use thousands::Separable;
 
+pub mod account;
+#[cfg(feature = "bank-directory")]
+pub mod bank;
+#[cfg(feature = "camt")]
+pub mod camt;
+pub mod checkdigit;
+pub mod epc;
+pub mod ebill;
 pub mod esr;
+pub mod installment;
 pub mod iso11649;
+#[cfg(feature = "pain001")]
+pub mod pain001;
 mod dimensions;
-mod label;
+pub mod label;
+mod metrics;
+pub mod reminder;
 pub mod render;
+pub mod swico;
+pub mod uid;
 
+pub use dimensions::FontSize;
 pub use label::Language;
 
 const IBAN_ALLOWED_COUNTRIES: [&str; 2] = ["CH", "LI"];
-const QR_IID_START: usize = 30000;
-const QR_IID_END: usize = 31999;
+const MAX_PAYLOAD_CHARS: usize = 997;
+const MAX_QR_VERSION: i16 = 25;
+const MAX_ALTERNATIVE_PROCEDURE_CHARS: usize = 100;
+/// Unstructured message and bill information together
+const MAX_ADDITIONAL_INFORMATION_CHARS: usize = 140;
 
 use dimensions::MM_TO_UU;
 const BILL_HEIGHT_IN_MM: f64 = 105.0;
@@ -30,10 +49,19 @@ const A4_WIDTH: f64 = A4_WIDTH_IN_MM * MM_TO_UU;
 const A4_HEIGHT_IN_MM: f64 = 297.0;
 const A4_HEIGHT: f64 = A4_HEIGHT_IN_MM * MM_TO_UU;
 
+/// The institution identification (IID) of a CH or LI IBAN: its digits 5 to 9
+#[cfg(any(feature = "bank-directory", feature = "pain001"))]
+fn iid(iban: &Iban) -> u32 {
+    iban.electronic_str()[4..9]
+        .parse()
+        .expect("This is a bug. Please report it.")
+}
+
 trait AddressExt {
     fn data_list(&self) -> Vec<String>;
 
-    fn as_paragraph(&self, max_width: usize) -> Vec<String>;
+    /// The lines of the address as printed on the bill, before wrapping
+    fn as_paragraph(&self) -> Vec<String>;
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -58,6 +86,34 @@ pub enum Error {
         "At maximum two alternative procedure with a maximum of 100 characters can be specified."
     )]
     AlternativeProcedure,
+    #[error("The font size of values on the payment part must be between 8 and 10 pt.")]
+    FontSize,
+    #[error(
+        "The QR code data has {length} characters, but at most 997 are allowed. Biggest contributors: {}.",
+        describe_contributors(.contributors)
+    )]
+    PayloadTooLong { length: usize, contributors: Vec<(PayloadPart, usize)> },
+    #[error("The QR code data needs a QR code of version {0}, but at most version 25 is allowed.")]
+    QrVersion(i16),
+    #[error("This looks like a QR reference (QRR), but: {0}")]
+    QrrReference(esr::Error),
+    #[error("This looks like a creditor reference (SCOR), but: {0}")]
+    ScorReference(iso11649::Error),
+    #[error("A reference must either be a QR reference of 27 digits, or a creditor reference starting with 'RF'.")]
+    UnrecognizedReference,
+    #[error("The bill information is invalid: {0}")]
+    BillInformation(swico::Error),
+    #[error("No installment plan can be made: {0}")]
+    Installment(installment::Error),
+    #[error("No reminder can be made for this bill: {0}")]
+    Reminder(reminder::Error),
+    #[error("No EPC QR code can be printed for this bill: {0}")]
+    Epc(epc::Error),
+    #[error("An SVG id may only have letters, digits, '-' and '_', but '{0}' was given.")]
+    InvalidSvgId(String),
+    #[cfg(feature = "bank-directory")]
+    #[error("The account cannot be used: {0}")]
+    Bank(bank::Error),
     #[error("An error with the QR code generation occured.")]
     Qr(#[from] QrError),
     #[error("An IO error occured.")]
@@ -66,11 +122,59 @@ pub enum Error {
     Pdf(#[from] svg2pdf::usvg::Error),
 }
 
+/// The parts of a bill which make up the data encoded in its QR code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadPart {
+    Header,
+    Creditor,
+    UltimateCreditor,
+    Amount,
+    Debtor,
+    Reference,
+    AdditionalInformation,
+    BillInformation,
+    AlternativeProcedures,
+}
+
+impl std::fmt::Display for PayloadPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            PayloadPart::Header                => "header and account",
+            PayloadPart::Creditor              => "creditor address",
+            PayloadPart::UltimateCreditor      => "ultimate creditor",
+            PayloadPart::Amount                => "amount",
+            PayloadPart::Debtor                => "debtor address",
+            PayloadPart::Reference             => "reference",
+            PayloadPart::AdditionalInformation => "additional information",
+            PayloadPart::BillInformation       => "bill information",
+            PayloadPart::AlternativeProcedures => "alternative procedures",
+        })
+    }
+}
+
+fn describe_contributors(contributors: &[(PayloadPart, usize)]) -> String {
+    contributors
+        .iter()
+        .map(|(part, length)| format!("{part} ({length} characters)"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Clone)]
 pub enum Address {
     Cobined(CombinedAddress),
     Structured(StructuredAddress),
 }
 
+impl Address {
+    pub fn name(&self) -> &str {
+        match self {
+            Address::Cobined(a) => &a.name,
+            Address::Structured(a) => &a.name,
+        }
+    }
+}
+
 impl AddressExt for Address {
     fn data_list(&self) -> Vec<String> {
         match self {
@@ -79,14 +183,15 @@ impl AddressExt for Address {
         }
     }
 
-    fn as_paragraph(&self, max_width: usize) -> Vec<String> {
+    fn as_paragraph(&self) -> Vec<String> {
         match self {
-            Address::Cobined(a) => a.as_paragraph(max_width),
-            Address::Structured(a) => a.as_paragraph(max_width),
+            Address::Cobined(a) => a.as_paragraph(),
+            Address::Structured(a) => a.as_paragraph(),
         }
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct CombinedAddress {
     name: String,
     line1: String,
@@ -126,14 +231,12 @@ impl AddressExt for CombinedAddress {
         ]
     }
 
-    fn as_paragraph(&self, max_width: usize) -> Vec<String> {
-        [self.name.clone(), self.line1.clone(), self.line2.clone()]
-            .iter()
-            .map(|line| textwrap::fill(line, max_width))
-            .collect()
+    fn as_paragraph(&self) -> Vec<String> {
+        vec![self.name.clone(), self.line1.clone(), self.line2.clone()]
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct StructuredAddress {
     pub name: String,
     pub street: String,
@@ -192,7 +295,7 @@ impl AddressExt for StructuredAddress {
         ]
     }
 
-    fn as_paragraph(&self, max_width: usize) -> Vec<String> {
+    fn as_paragraph(&self) -> Vec<String> {
         let maybe_prefix = if self.country == CountryCode::CHE {
             "".to_string() } else {
             format!("{}-", self.country.alpha2().to_owned())
@@ -206,9 +309,6 @@ impl AddressExt for StructuredAddress {
                 self.city,
             ),
         ]
-        .into_iter()
-        .map(|line| textwrap::fill(&line, max_width))
-        .collect()
     }
 }
 
@@ -227,6 +327,7 @@ impl std::fmt::Display for Currency {
     }
 }
 
+#[derive(Debug)]
 pub struct QRBill {
     account: Iban,
     creditor: Address,
@@ -237,34 +338,119 @@ pub struct QRBill {
     reference: Reference,
     /// Extra information aimed for the bill recipient.
     pub extra_infos: Option<String>,
+    /// Structured information for the software of the bill recipient.
+    bill_information: Option<swico::S1>,
     /// Two additional fields for alternative payment schemes.
     alternative_processes: Vec<String>,
     /// Language of the output.
     language: Language,
+    /// The texts not defined by the standard, in `language` unless replaced.
+    texts: label::Texts,
+    /// Font size of the values on the payment part.
+    font_size: FontSize,
     /// Print a horizontal line at the top of the bill.
     line_top: bool,
+    /// Print "Separate before paying in" above the top line on full-page
+    /// output.
+    separate_note: bool,
     /// Print a vertical line between the receipt and the bill itself.
     line_mid: bool,
+    /// Where to print an EPC QR code on full-page output, if at all.
+    epc_code: Option<epc::Position>,
+    /// Print the due date under a heading of its own.
+    due_date_heading: bool,
+    /// Only notifies of a payment made by other means: see `notification`.
+    notification: bool,
+    /// Distinguishes the SVG ids of this bill from those of any other bill
+    /// rendered onto the same page: see `QRBillOptions::svg_id`.
+    id: String,
 }
 
+#[derive(Clone)]
 pub struct QRBillOptions {
     pub account: Iban,
     pub creditor: Address,
     pub amount: Option<f64>,
     pub currency: Currency,
+    /// The date the bill is due. With `bill_information` of a document date,
+    /// it is encoded in the QR code as net payment term; otherwise it is only
+    /// printed.
     pub due_date: Option<NaiveDate>,
     pub debtor: Option<Address>,
     pub reference: Reference,
     /// Extra information aimed for the bill recipient.
     pub extra_infos: Option<String>,
+    /// Swico S1 bill information, such as the invoice number and date, for
+    /// the software of the bill recipient. Together with `extra_infos` it may
+    /// have at most 140 characters.
+    pub bill_information: Option<swico::S1>,
     /// Two additional fields for alternative payment schemes.
     pub alternative_processes: Vec<String>,
     /// Language of the output.
     pub language: Language,
+    /// Replaces the texts which are not defined by the standard, such as the
+    /// notice of `QRBill::notification`. `None` uses those of `language`.
+    pub texts: Option<label::Texts>,
+    /// Font size of the values on the payment part; their headings are 2 pt
+    /// smaller. Text which does not fit is printed smaller still, down to
+    /// `FontSize::SMALLEST`.
+    pub font_size: FontSize,
     /// Print a horizontal line at the top of the bill.
     pub top_line: bool,
+    /// Print "Separate before paying in", in the language of the bill, above
+    /// the top line on full-page output, as asked for by the style guide when
+    /// the paper is not perforated. Only with `top_line`.
+    pub separate_note: bool,
     /// Print a vertical line between the receipt and the bill itself.
     pub payment_line: bool,
+    /// Print an EPC QR code for SEPA credit transfers at the given position
+    /// on full-page output, such as `epc::Position::ABOVE_PAYMENT_PART` if the
+    /// letter above the bill leaves room for it. Only for bills in EUR without
+    /// QR reference.
+    pub epc_code: Option<epc::Position>,
+    /// Print the due date under a heading of its own, "Payable by" in
+    /// English. The heading is not defined by the Swiss Payment Standards,
+    /// which only know the due date as part of the bill information.
+    pub due_date_heading: bool,
+    /// Suffix of the ids of the SVG elements of the bill, such as
+    /// `swiss-cross-{svg_id}`: letters, digits, `-` and `_`. `None` derives it
+    /// from the QR code data, so that the same bill is always rendered the
+    /// same. Give the same bill rendered more than once onto one page
+    /// different ids.
+    pub svg_id: Option<String>,
+}
+
+impl QRBillOptions {
+    /// Options for a bill of any amount in CHF, without debtor, reference or
+    /// additional information, printed in English with the lines for cutting
+    /// it out.
+    ///
+    /// Fields added in later versions get a default which keeps the previous
+    /// output, so callers who complete their options with
+    /// `..QRBillOptions::new(account, creditor)` keep compiling.
+    pub fn new(account: Iban, creditor: Address) -> Self {
+        Self {
+            account,
+            creditor,
+            amount: None,
+            currency: Currency::SwissFranc,
+            due_date: None,
+            debtor: None,
+            reference: Reference::None,
+            extra_infos: None,
+            bill_information: None,
+            alternative_processes: vec![],
+            language: Language::English,
+            texts: None,
+            font_size: FontSize::RECOMMENDED,
+            top_line: true,
+            separate_note: false,
+            payment_line: true,
+            epc_code: None,
+            due_date_heading: true,
+            svg_id: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -275,6 +461,26 @@ pub enum Reference {
 }
 
 impl Reference {
+    /// Recognise and validate a reference typed or pasted in by a person.
+    ///
+    /// Whitespace is ignored. References starting with `RF` are creditor
+    /// references (SCOR), references starting with a digit are QR references
+    /// (QRR). Either must have valid check digits. An empty input means there
+    /// is no reference.
+    pub fn from_user_input(input: &str) -> Result<Self, Error> {
+        let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+        match compact.chars().next() {
+            None => Ok(Reference::None),
+            Some(c) if c.is_ascii_digit() => compact.parse()
+                .map(Reference::Qrr)
+                .map_err(Error::QrrReference),
+            Some(_) if compact.get(..2).is_some_and(|rf| rf.eq_ignore_ascii_case("RF")) => iso11649::Iso11649::parse(&compact)
+                .map(Reference::Scor)
+                .map_err(Error::ScorReference),
+            Some(_) => Err(Error::UnrecognizedReference),
+        }
+    }
+
     fn data_list(&self) -> Vec<String> {
         match self {
             Reference::Qrr(esr) => vec!["QRR".to_string(), esr.to_raw()],
@@ -310,14 +516,32 @@ impl QRBill {
     const CODING: usize = 1;
 
     /// Creates a new QR-Bill which can be rendered onto an SVG.
+    ///
+    /// VAT details in the bill information must add up to the amount, if
+    /// both are given.
     pub fn new(options: QRBillOptions) -> Result<Self, Error> {
+        if let Some(vat_details) = options.bill_information.as_ref().and_then(|s1| s1.vat_details.as_ref()) {
+            vat_details.validate(options.amount).map_err(Error::BillInformation)?;
+        }
+        Self::create(options, false)
+    }
+
+    /// Creates a bill which only notifies the debtor of a payment made by
+    /// other means, such as eBill or direct debit (LSV+).
+    ///
+    /// The amount is set to 0.00, and "DO NOT USE FOR PAYMENT", in the
+    /// language of the bill, is put in front of the additional information
+    /// and printed prominently on both the receipt and the payment part.
+    pub fn notification(options: QRBillOptions) -> Result<Self, Error> {
+        Self::create(QRBillOptions { amount: Some(0.0), ..options }, true)
+    }
+
+    fn create(options: QRBillOptions, notification: bool) -> Result<Self, Error> {
         if !IBAN_ALLOWED_COUNTRIES.contains(&options.account.country_code()) {
             return Err(Error::InvalidIban);
         }
-        let iban_iid = options.account.electronic_str()[4..9]
-            .parse()
-            .expect("This is a bug. Please report it.");
-        let _account_is_qriban = (QR_IID_START..=QR_IID_END).contains(&iban_iid);
+        #[cfg(feature = "bank-directory")]
+        bank::validate_iid(&options.account).map_err(Error::Bank)?;
 
         // TODO validate ESR reference number
 
@@ -332,52 +556,135 @@ impl QRBill {
         if options.alternative_processes.len() > 2 {
             return Err(Error::AlternativeProcedure);
         }
-        if options.alternative_processes.iter().any(|v| v.len() > 100) {
+        if options.alternative_processes.iter().any(|v| v.chars().count() > MAX_ALTERNATIVE_PROCEDURE_CHARS) {
             return Err(Error::AlternativeProcedure);
         }
 
-        Ok(Self {
+        let bill_information = match (options.bill_information, options.due_date) {
+            (Some(s1), Some(due_date)) => Some(s1.with_due_date(due_date).map_err(Error::BillInformation)?),
+            (s1, _) => s1,
+        };
+
+        let mut bill = Self {
             account: options.account,
             creditor: options.creditor,
             amount: options.amount,
             currency: options.currency,
-            due_date: options.due_date,
+            due_date: options.due_date.or_else(|| bill_information.as_ref().and_then(swico::S1::due_date)),
             debtor: options.debtor,
             reference: options.reference,
             extra_infos: options.extra_infos,
+            bill_information,
             alternative_processes: options.alternative_processes,
+            texts: options.texts.unwrap_or_else(|| label::Texts::for_language(options.language)),
             language: options.language,
+            font_size: options.font_size,
             line_top: options.top_line,
+            separate_note: options.separate_note,
             line_mid: options.payment_line,
-        })
+            epc_code: options.epc_code,
+            due_date_heading: options.due_date_heading,
+            notification,
+            id: String::new(),
+        };
+        let message_chars = bill.unstructured_message().map_or(0, |m| m.chars().count())
+            + bill.bill_information.as_ref().map_or(0, |s1| s1.to_string().chars().count());
+        if message_chars > MAX_ADDITIONAL_INFORMATION_CHARS {
+            return Err(Error::ExtraInfos);
+        }
+        bill.check_payload()?;
+        if bill.epc_code.is_some() {
+            epc::Epc::try_from(&bill).map_err(Error::Epc)?;
+        }
+        bill.id = match options.svg_id {
+            Some(id) if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') => id,
+            Some(id) => return Err(Error::InvalidSvgId(id)),
+            None => format!("{:016x}", content_id(&bill.qr_data())),
+        };
+        Ok(bill)
+    }
+
+    /// The unstructured message (Ustrd) of the payload: the additional
+    /// information, preceded by the notice of a notification.
+    fn unstructured_message(&self) -> Option<String> {
+        let notice = self.notification.then_some(&self.texts.do_not_use_for_payment);
+        match (notice, &self.extra_infos) {
+            (Some(notice), Some(info)) => Some(format!("{notice} {info}")),
+            (Some(notice), None) => Some(notice.to_string()),
+            (None, info) => info.clone(),
+        }
+    }
+
+    /// Make sure the payload fits into a QR code as permitted by the spec: at
+    /// most 997 characters, in a QR code of version 25 or lower with error
+    /// correction level M.
+    fn check_payload(&self) -> Result<(), Error> {
+        let length = self.qr_data().chars().count();
+        if length > MAX_PAYLOAD_CHARS {
+            // Each line counts together with the line break which ends it
+            let mut contributors: Vec<_> = self.qr_data_parts().into_iter()
+                .map(|(part, lines)| (part, lines.iter().map(|l| l.chars().count() + 1).sum::<usize>()))
+                .collect();
+            contributors.sort_by(|(_, a), (_, b)| b.cmp(a));
+            contributors.truncate(3);
+            return Err(Error::PayloadTooLong { length, contributors });
+        }
+        let version = self.qr_version()?;
+        if version > MAX_QR_VERSION {
+            return Err(Error::QrVersion(version));
+        }
+        Ok(())
+    }
+
+    /// The version (size) of the QR code which encodes this bill.
+    pub fn qr_version(&self) -> Result<i16, Error> {
+        match self.qr_code()?.version() {
+            qrcode::Version::Normal(v) | qrcode::Version::Micro(v) => Ok(v),
+        }
     }
 
     /// Return data to be encoded in the QR code in the standard text representation of a list of strings.
     pub fn qr_data(&self) -> String {
-        let mut data = vec![
-            Self::QR_TYPE.to_string(),
-            Self::VERSION.to_string(),
-            Self::CODING.to_string(),
-            self.account.electronic_str().to_string(),
-        ];
-        data.extend(self.creditor.data_list());
-        data.extend(vec!["".into(); 7]);
-        data.extend(vec![
-            self.amount.map(|v| format!("{:.2}", v)).unwrap_or_default(),
-            self.currency.to_string(),
-        ]);
-        data.extend(
-            self.debtor
+        self.qr_data_parts()
+            .into_iter()
+            .flat_map(|(_, lines)| lines)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The lines of `qr_data`, grouped by the part of the bill they encode.
+    fn qr_data_parts(&self) -> Vec<(PayloadPart, Vec<String>)> {
+        vec![
+            (PayloadPart::Header, vec![
+                Self::QR_TYPE.to_string(),
+                Self::VERSION.to_string(),
+                Self::CODING.to_string(),
+                self.account.electronic_str().to_string(),
+            ]),
+            (PayloadPart::Creditor, self.creditor.data_list()),
+            (PayloadPart::UltimateCreditor, vec!["".into(); 7]),
+            (PayloadPart::Amount, vec![
+                self.amount.map(|v| format!("{:.2}", v)).unwrap_or_default(),
+                self.currency.to_string(),
+            ]),
+            (PayloadPart::Debtor, self.debtor
                 .as_ref()
                 .map(|v| v.data_list())
-                .unwrap_or_else(|| vec!["".into(); 7]),
-        );
-        data.extend(self.reference.data_list());
-        data.extend(vec![self.extra_infos.clone().unwrap_or_default()]);
-        data.push("EPD".to_string());
-        data.extend(self.alternative_processes.clone());
-
-        data.join("\n")
+                .unwrap_or_else(|| vec!["".into(); 7])),
+            (PayloadPart::Reference, self.reference.data_list()),
+            (PayloadPart::AdditionalInformation, vec![
+                self.unstructured_message().unwrap_or_default(),
+                "EPD".to_string(),
+            ]),
+            // Optional, but must be given, if empty, for the alternative
+            // procedures to follow it.
+            (PayloadPart::BillInformation, match &self.bill_information {
+                Some(s1) => vec![s1.to_string()],
+                None if self.alternative_processes.is_empty() => vec![],
+                None => vec!["".to_string()],
+            }),
+            (PayloadPart::AlternativeProcedures, self.alternative_processes.clone()),
+        ]
     }
 
     /// Writes the represented QR-Bill into an SVG file.
@@ -413,6 +720,52 @@ impl QRBill {
         Ok(())
     }
 
+    /// Returns a PDF with one page per bill, e.g. for all bills of an
+    /// installment plan.
+    ///
+    /// * `full_page`: Makes every page the size of a full A4 page.
+    pub fn create_pdf(bills: &[QRBill], full_page: bool) -> Result<Vec<u8>, Error> {
+        use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+
+        let mut options = svg2pdf::usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+
+        let mut alloc = Ref::new(1);
+        let catalog_id = alloc.bump();
+        let page_tree_id = alloc.bump();
+        let mut pdf = Pdf::new();
+        let mut page_ids = Vec::with_capacity(bills.len());
+        for bill in bills {
+            let tree = svg2pdf::usvg::Tree::from_str(&bill.create_svg(full_page)?, &options)?;
+            let (chunk, svg_id) = svg2pdf::to_chunk(&tree, svg2pdf::ConversionOptions::default());
+            // Every chunk numbers its objects from 1, so move them behind
+            // the objects of the previous pages.
+            let mut ids = std::collections::HashMap::new();
+            let chunk = chunk.renumber(|old| *ids.entry(old).or_insert_with(|| alloc.bump()));
+            let svg_id = ids[&svg_id];
+
+            let page_id = alloc.bump();
+            let content_id = alloc.bump();
+            let svg_name = Name(b"S1");
+            let (width, height) = (tree.size().width(), tree.size().height());
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, width, height));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            page.resources().x_objects().pair(svg_name, svg_id);
+            page.finish();
+
+            let mut content = Content::new();
+            content.transform([width, 0.0, 0.0, height, 0.0, 0.0]).x_object(svg_name);
+            pdf.stream(content_id, &content.finish());
+            pdf.extend(&chunk);
+            page_ids.push(page_id);
+        }
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id).count(page_ids.len() as i32).kids(page_ids);
+        Ok(pdf.finish())
+    }
+
     /// Returns a string containing the SVG representing the QR-Bill
     ///
     /// * `full_page`: Makes the generated SVG the size of a full A4 page.
@@ -421,7 +774,7 @@ impl QRBill {
         let (h_in_mm, h) = if full_page { (  A4_HEIGHT_IN_MM,   A4_HEIGHT) }
         else                            { (BILL_HEIGHT_IN_MM, BILL_HEIGHT) };
         let document = Document::new()
-            .add(svg::node::element::Style::new(crate::dimensions::make_svg_styles()))
+            .add(svg::node::element::Style::new(render::Render::styles(self)))
             .set("width", format!("{A4_WIDTH_IN_MM}mm"))
             .set("height", format!("{h_in_mm}mm"))
             .set("viewBox", format!("0 0 {A4_WIDTH} {h}"));
@@ -444,13 +797,21 @@ impl QRBill {
 
         document = document.add(bill_group);
 
+        if let (true, Some(position)) = (full_page, self.epc_code) {
+            document = document.add(self.section_epc(position)?);
+        }
+
         Ok(document.to_string())
     }
 
     /// Renders to an A4 page, adding the bill in a group element.
     ///
-    /// Also adds a note about separating the bill.
-    fn transform_to_full_page(&self, group: Group) -> Group {
+    /// Also adds the note about separating the bill above its top line, if
+    /// asked for.
+    fn transform_to_full_page(&self, mut group: Group) -> Group {
+        if self.line_top && self.separate_note {
+            group = group.add(self.separate_note());
+        }
         let y_offset = A4_HEIGHT - BILL_HEIGHT;
         group.set("transform", format!("translate(0, {})", y_offset))
     }
@@ -479,12 +840,13 @@ fn format_amount(amount: f64) -> String {
     format!("{:.2}", amount).separate_with_spaces()
 }
 
-// def wrap_infos(infos) {
-//     for text in infos:
-//         while(text) {
-//             yield text[:MAX_CHARS_PAYMENT_LINE]
-//             text = text[MAX_CHARS_PAYMENT_LINE:]
-
+/// A 64-bit FNV-1a hash of `data`. Unlike `std::hash::DefaultHasher`, its
+/// value is the same with every version of Rust.
+fn content_id(data: &str) -> u64 {
+    data.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
 
 pub fn chunked(unchunked: &str) -> String {
     unchunked
@@ -495,3 +857,317 @@ pub fn chunked(unchunked: &str) -> String {
         .collect::<Vec<String>>()
         .join(" ")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    pub(crate) fn address(name: String) -> Address {
+        // Bypass the length checks of `StructuredAddress::new`, which callers
+        // can do too, as the fields are public
+        Address::Structured(StructuredAddress {
+            name,
+            street:       "Musterstrasse".into(),
+            house_number: "123".into(),
+            postal_code:  "8000".into(),
+            city:         "Seldwyla".into(),
+            country:      CountryCode::CHE,
+        })
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    pub(crate) fn options(name: String, alternative_processes: Vec<String>) -> QRBillOptions {
+        QRBillOptions {
+            account: "CH1430000000800004703".parse().unwrap(),
+            creditor: address(name.clone()),
+            amount: Some(1949.75),
+            currency: Currency::SwissFranc,
+            due_date: None,
+            debtor: Some(address(name)),
+            reference: Reference::None,
+            extra_infos: None,
+            bill_information: None,
+            alternative_processes,
+            language: Language::English,
+            texts: None,
+            font_size: FontSize::RECOMMENDED,
+            top_line: true,
+            separate_note: true,
+            payment_line: true,
+            epc_code: None,
+            due_date_heading: true,
+            svg_id: None,
+        }
+    }
+
+    #[test]
+    fn new_options_keep_previous_output() {
+        let name = "Max Muster".to_string();
+        let options = QRBillOptions {
+            amount: Some(1949.75),
+            debtor: Some(address(name.clone())),
+            ..QRBillOptions::new("CH1430000000800004703".parse().unwrap(), address(name.clone()))
+        };
+        assert!(options.top_line && options.payment_line && options.due_date_heading);
+        assert!(!options.separate_note && options.epc_code.is_none());
+        assert_eq!(QRBill::new(options).unwrap().qr_data(), QRBill::new(tests::options(name, vec![])).unwrap().qr_data());
+    }
+
+    #[cfg(feature = "bank-directory")]
+    #[test]
+    fn unknown_iid() {
+        let options = QRBillOptions {
+            account: "CH4431999123000889012".parse().unwrap(),
+            ..options("Max Muster".into(), vec![])
+        };
+        assert!(matches!(QRBill::new(options), Err(Error::Bank(bank::Error::UnknownIid(31999)))));
+    }
+
+    #[rstest]
+    #[case(  0,  9)]
+    #[case(300, 22)]
+    fn qr_version(#[case] extra_name_chars: usize, #[case] expected: i16) {
+        let name = format!("Max Muster & Söhne{}", "x".repeat(extra_name_chars));
+        let bill = QRBill::new(options(name, vec![])).unwrap();
+        assert_eq!(bill.qr_version().unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(""                                , "NON" , ""                           )]
+    #[case("21 00000 00003 13947 14300 09017", "QRR" , "210000000003139471430009017")]
+    #[case("RF18 5390 0754 7034"             , "SCOR", "RF18539007547034"           )]
+    #[case(" rf18539007547034 "              , "SCOR", "RF18539007547034"           )]
+    fn reference_from_user_input(#[case] input: &str, #[case] kind: &str, #[case] data: &str) {
+        let reference = Reference::from_user_input(input).unwrap();
+        assert_eq!(reference.data_list(), vec![kind.to_string(), data.to_string()]);
+    }
+
+    #[rstest]
+    #[case("21 00000 00003 13947 14300 09018", "This looks like a QR reference (QRR), but: The check digit should be 7, but is 8.")]
+    #[case("21 00000 00003 13947 14300 0901" , "This looks like a QR reference (QRR), but: A QR reference must have 27 digits, but 26 were given.")]
+    #[case("RF19 5390 0754 7034"             , "This looks like a creditor reference (SCOR), but: The check digits should be 18, but are 19.")]
+    #[case("ABC 123"                         , "A reference must either be a QR reference of 27 digits, or a creditor reference starting with 'RF'.")]
+    #[case("R"                               , "A reference must either be a QR reference of 27 digits, or a creditor reference starting with 'RF'.")]
+    fn reference_from_user_input_rejects(#[case] input: &str, #[case] message: &str) {
+        assert_eq!(Reference::from_user_input(input).unwrap_err().to_string(), message);
+    }
+
+    #[test]
+    fn swiss_cross_ids_are_unique() {
+        let a = QRBill::new(options("A".into(), vec![])).unwrap().create_svg(false).unwrap();
+        let b = QRBill::new(options("B".into(), vec![])).unwrap().create_svg(false).unwrap();
+        let id = |svg: &str| svg.split("id=\"swiss-cross-").nth(1).unwrap().split('"').next().unwrap().to_string();
+        assert_ne!(id(&a), id(&b));
+    }
+
+    #[test]
+    fn svg_ids_given_by_caller() {
+        let svg = |svg_id: &str| QRBill::new(QRBillOptions { svg_id: Some(svg_id.into()), ..options("A".into(), vec![]) })
+            .and_then(|bill| bill.create_svg(false));
+        let svg = svg("invoice-42_2").unwrap();
+        assert!(svg.contains(r#"id="swiss-cross-invoice-42_2""#), "{svg}");
+        assert!(!svg.contains(r#"id="swiss-cross""#));
+        let invalid = QRBill::new(QRBillOptions { svg_id: Some("a b".into()), ..options("A".into(), vec![]) });
+        assert!(matches!(invalid, Err(Error::InvalidSvgId(id)) if id == "a b"));
+    }
+
+    #[test]
+    fn svg_is_reproducible() {
+        let svg = || QRBill::new(options("A".into(), vec![])).unwrap().create_svg(false).unwrap();
+        assert_eq!(svg(), svg());
+    }
+
+    #[test]
+    fn multibyte_payload_needs_too_big_a_qr_code() {
+        // Fewer than 997 characters, but each 'ü' takes up two bytes
+        let name = "ü".repeat(300);
+        let error = QRBill::new(options(name, vec![])).err().unwrap();
+        assert!(matches!(error, Error::QrVersion(version) if version > 25), "{error}");
+    }
+
+    #[test]
+    fn payload_too_long_names_biggest_contributors() {
+        let procedures = vec!["eBill/B/".to_string() + &"x".repeat(92); 2];
+        let name = "y".repeat(350);
+        let error = QRBill::new(options(name, procedures)).err().unwrap();
+        let Error::PayloadTooLong { length, contributors } = &error else { panic!("{error}") };
+        assert!(*length > 997);
+        let parts: Vec<_> = contributors.iter().map(|(part, _)| *part).collect();
+        assert_eq!(parts, vec![PayloadPart::Creditor, PayloadPart::Debtor, PayloadPart::AlternativeProcedures]);
+        assert!(error.to_string().contains("alternative procedures (202 characters)"), "{error}");
+    }
+
+    #[rstest]
+    #[case(Language::German , None          , "NICHT ZUR ZAHLUNG VERWENDEN")]
+    #[case(Language::English, None          , "DO NOT USE FOR PAYMENT")]
+    #[case(Language::French , Some("eBill") , "NE PAS UTILISER POUR LE PAIEMENT eBill")]
+    #[case(Language::Italian, Some("LSV+")  , "NON UTILIZZARE PER IL PAGAMENTO LSV+")]
+    fn notification(#[case] language: Language, #[case] extra_infos: Option<&str>, #[case] message: &str) {
+        let bill = QRBill::notification(QRBillOptions {
+            language,
+            extra_infos: extra_infos.map(str::to_string),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let data = bill.qr_data();
+        let lines: Vec<_> = data.lines().collect();
+        assert_eq!(lines[18..20], ["0.00", "CHF"]);
+        assert_eq!(lines[29..31], [message, "EPD"]);
+        let notice = label::DO_NOT_USE_FOR_PAYMENT.to(language);
+        assert_eq!(bill.create_svg(false).unwrap().matches(notice).count(), 2, "on the receipt and the payment part");
+    }
+
+    #[test]
+    fn notification_with_replaced_texts() {
+        let texts = label::Texts {
+            do_not_use_for_payment: "BETG DUVRAR PER PAJAR".into(),
+            ..label::Texts::for_language(Language::German)
+        };
+        let bill = QRBill::notification(QRBillOptions {
+            language: Language::German,
+            texts: Some(texts),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        assert_eq!(bill.qr_data().lines().nth(29), Some("BETG DUVRAR PER PAJAR"));
+        let svg = bill.create_svg(false).unwrap();
+        assert_eq!(svg.matches("BETG DUVRAR PER PAJAR").count(), 2, "on the receipt and the payment part");
+        assert!(svg.contains(label::Labels::for_language(Language::German).payment_part), "official headings are kept");
+    }
+
+    #[test]
+    fn notification_message_too_long() {
+        let bill = QRBill::notification(QRBillOptions {
+            extra_infos: Some("x".repeat(120)),
+            ..options("Max Muster".into(), vec![])
+        });
+        assert!(matches!(bill, Err(Error::ExtraInfos)));
+    }
+
+    #[rstest]
+    #[case(None                        , Some(date(2024, 3, 31)), None                                 , Some(date(2024, 3, 31)))]
+    #[case(Some("//S1/10/42/11/240301"), Some(date(2024, 3, 31)), Some("//S1/10/42/11/240301/40/0:30"), Some(date(2024, 3, 31)))]
+    #[case(Some("//S1/11/240301/40/0:10"), None                 , Some("//S1/11/240301/40/0:10")      , Some(date(2024, 3, 11)))]
+    #[case(Some("//S1/10/42")          , None                   , Some("//S1/10/42")                   , None                   )]
+    fn due_date_in_bill_information(
+        #[case] bill_information: Option<&str>,
+        #[case] due_date: Option<NaiveDate>,
+        #[case] encoded: Option<&str>,
+        #[case] expected_due_date: Option<NaiveDate>,
+    ) {
+        let bill = QRBill::new(QRBillOptions {
+            bill_information: bill_information.map(|text| text.parse().unwrap()),
+            due_date,
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let data = bill.qr_data();
+        let lines: Vec<_> = data.lines().collect();
+        assert_eq!(lines[30..].first().copied(), Some("EPD"));
+        assert_eq!(lines.get(31).copied(), encoded);
+        assert_eq!(bill.due_date, expected_due_date);
+    }
+
+    #[test]
+    fn due_date_without_document_date() {
+        let bill = QRBill::new(QRBillOptions {
+            bill_information: Some("//S1/10/42".parse().unwrap()),
+            due_date: Some(date(2024, 3, 31)),
+            ..options("Max Muster".into(), vec![])
+        });
+        assert!(matches!(bill, Err(Error::BillInformation(swico::Error::MissingDocumentDate))));
+    }
+
+    #[rstest]
+    #[case(Some(1080.0), true )]
+    #[case(None        , true )]
+    #[case(Some(1.0)   , false)]
+    fn vat_details_match_amount(#[case] amount: Option<f64>, #[case] valid: bool) {
+        let bill = QRBill::new(QRBillOptions {
+            amount,
+            bill_information: Some("//S1/32/8:1000".parse().unwrap()),
+            ..options("Max Muster".into(), vec![])
+        });
+        match valid {
+            true => assert!(bill.is_ok()),
+            false => assert!(matches!(bill, Err(Error::BillInformation(swico::Error::AmountMismatch { .. })))),
+        }
+    }
+
+    #[rstest]
+    #[case(true , 2)]
+    #[case(false, 0)]
+    fn optional_due_date_heading(#[case] due_date_heading: bool, #[case] expected: usize) {
+        let bill = QRBill::new(QRBillOptions {
+            bill_information: Some("//S1/11/240301".parse().unwrap()),
+            due_date: Some(date(2024, 3, 31)),
+            due_date_heading,
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let svg = bill.create_svg(false).unwrap();
+        assert_eq!(svg.matches("31.03.2024").count(), expected, "on the receipt and the payment part");
+        assert_eq!(svg.matches("//S1/11/240301/40/0:30").count(), 1, "in the additional information");
+    }
+
+    #[rstest]
+    #[case(Language::English, true , true , true , Some("Separate before paying in"))]
+    #[case(Language::German , true , true , true , Some("Vor der Einzahlung abzutrennen"))]
+    #[case(Language::French , true , true , false, None)]
+    #[case(Language::Italian, true , false, true , None)]
+    #[case(Language::English, false, true , true , None)]
+    fn separate_note_above_top_line(
+        #[case] language: Language,
+        #[case] full_page: bool,
+        #[case] top_line: bool,
+        #[case] separate_note: bool,
+        #[case] expected: Option<&str>,
+    ) {
+        let bill = QRBill::new(QRBillOptions {
+            language,
+            top_line,
+            separate_note,
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let svg = bill.create_svg(full_page).unwrap();
+        let note = label::SEPARATE_BEFORE_PAYING_IN.to(language);
+        assert_eq!(svg.contains(note).then_some(note), expected);
+    }
+
+    #[test]
+    fn separate_note_with_replaced_text() {
+        let mut texts = label::Texts::for_language(Language::German);
+        texts.separate_before_paying_in = "Vor dr Izahlig abtrenne".into();
+        let bill = QRBill::new(QRBillOptions {
+            language: Language::German,
+            texts: Some(texts),
+            separate_note: true,
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let svg = bill.create_svg(true).unwrap();
+        assert!(svg.contains(r#"class="separate-note""#), "{svg}");
+        assert!(svg.contains("Vor dr Izahlig abtrenne"));
+        assert!(!svg.contains(label::SEPARATE_BEFORE_PAYING_IN.to(Language::German)));
+    }
+
+    #[test]
+    fn alternative_procedures_follow_bill_information() {
+        let bill = QRBill::new(options("Max Muster".into(), vec!["eBill/B/peter@sample.ch".into()])).unwrap();
+        let data = bill.qr_data();
+        let lines: Vec<_> = data.lines().collect();
+        assert_eq!(lines[30..], ["EPD", "", "eBill/B/peter@sample.ch"]);
+    }
+
+    #[test]
+    fn bill_information_counts_towards_message_length() {
+        let bill = |invoice_number: &str| QRBill::new(QRBillOptions {
+            extra_infos: Some("x".repeat(120)),
+            bill_information: Some(swico::S1 { invoice_number: Some(invoice_number.into()), ..Default::default() }),
+            ..options("Max Muster".into(), vec![])
+        });
+        // "//S1/10/" and the invoice number
+        assert!(bill(&"1".repeat(12)).is_ok());
+        assert!(matches!(bill(&"1".repeat(13)), Err(Error::ExtraInfos)));
+    }
+}
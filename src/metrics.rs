@@ -0,0 +1,217 @@
+//! Measuring and wrapping text with the metrics of the font used on the bill.
+
+// 3.4 // Fonts and font sizes
+//
+// Only the sans-serif fonts Arial, Frutiger, Helvetica and Liberation Sans are
+// permitted in black.
+//
+// Arial and Liberation Sans were designed to be metrically compatible with
+// Helvetica: every glyph has the same advance width. The widths below are
+// those of the Adobe Helvetica and Helvetica-Bold AFM files, in thousandths of
+// an em, for printable ASCII and Latin-1.
+
+use deunicode::deunicode_char;
+
+use crate::dimensions::{Font, Length, Weight};
+
+/// Advance widths of the characters ' ' (U+0020) to '~' (U+007E)
+#[rustfmt::skip]
+const ASCII_NORMAL: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, //   ! " # $ % & ' ( ) * + , - . /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // 0 1 2 3 4 5 6 7 8 9 : ; < = > ?
+   1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // @ A B C D E F G H I J K L M N O
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // P Q R S T U V W X Y Z [ \ ] ^ _
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // ` a b c d e f g h i j k l m n o
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,      // p q r s t u v w x y z { | } ~
+];
+
+#[rustfmt::skip]
+const ASCII_BOLD: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// Advance widths of the characters U+00A0 (no-break space) to U+00FF ('ÿ')
+#[rustfmt::skip]
+const LATIN1_NORMAL: [u16; 96] = [
+    278, 333, 556, 556, 556, 556, 260, 556, 333, 737, 370, 556, 584, 333, 737, 333, //   ¡ ¢ £ ¤ ¥ ¦ § ¨ © ª « ¬ - ® ¯
+    400, 584, 333, 333, 333, 556, 537, 278, 333, 333, 365, 556, 834, 834, 834, 611, // ° ± ² ³ ´ µ ¶ · ¸ ¹ º » ¼ ½ ¾ ¿
+    667, 667, 667, 667, 667, 667,1000, 722, 667, 667, 667, 667, 278, 278, 278, 278, // À Á Â Ã Ä Å Æ Ç È É Ê Ë Ì Í Î Ï
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611, // Ð Ñ Ò Ó Ô Õ Ö × Ø Ù Ú Û Ü Ý Þ ß
+    556, 556, 556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278, // à á â ã ä å æ ç è é ê ë ì í î ï
+    556, 556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500, 556, 500, // ð ñ ò ó ô õ ö ÷ ø ù ú û ü ý þ ÿ
+];
+
+#[rustfmt::skip]
+const LATIN1_BOLD: [u16; 96] = [
+    278, 333, 556, 556, 556, 556, 280, 556, 333, 737, 370, 556, 584, 333, 737, 333,
+    400, 584, 333, 333, 333, 611, 556, 278, 333, 333, 365, 556, 834, 834, 834, 611,
+    722, 722, 722, 722, 722, 722,1000, 722, 667, 667, 667, 667, 278, 278, 278, 278,
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611,
+    556, 556, 556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278, 278,
+    611, 611, 611, 611, 611, 611, 611, 584, 611, 611, 611, 611, 611, 556, 611, 556,
+];
+
+/// Width of a full em: used for anything that cannot be measured, so that
+/// unknown characters err on the side of taking up too much space.
+const EM: u16 = 1000;
+
+pub const ELLIPSIS: char = '…';
+
+/// Advance width of a single character in thousandths of an em
+fn advance(c: char, weight: Weight) -> u16 {
+    let bold = weight == Weight::Bold;
+    match c as u32 {
+        0x20..=0x7E => if bold { ASCII_BOLD [c as usize - 0x20] } else { ASCII_NORMAL [c as usize - 0x20] },
+        0xA0..=0xFF => if bold { LATIN1_BOLD[c as usize - 0xA0] } else { LATIN1_NORMAL[c as usize - 0xA0] },
+        _ => match c {
+            '…' | '—' => EM,
+            '€' | '–' => 556,
+            '‘' | '’' | '‚' => if bold { 278 } else { 222 },
+            '“' | '”' | '„' => if bold { 500 } else { 333 },
+            // Everything else is measured as its closest ASCII transliteration
+            _ => match deunicode_char(c) {
+                Some(ascii) if !ascii.is_empty() && ascii.chars().all(|a| a.is_ascii_graphic() || a == ' ') =>
+                    ascii.chars().map(|a| advance(a, weight)).sum(),
+                _ => EM,
+            },
+        },
+    }
+}
+
+/// The width `text` takes up when rendered in `font`
+pub fn text_width(text: &str, font: &Font) -> Length {
+    let ems: u32 = text.chars().map(|c| advance(c, font.weight) as u32).sum();
+    Length::uu(font.size.as_uu() * ems as f64 / EM as f64)
+}
+
+fn fits(text: &str, font: &Font, width: Length) -> bool {
+    text_width(text, font).as_uu() <= width.as_uu()
+}
+
+/// Break `text` into lines no wider than `width` when rendered in `font`.
+///
+/// Lines are broken between words where possible. A single word which is too
+/// wide on its own is broken between characters. Explicit newlines in `text`
+/// are kept.
+pub fn wrap(text: &str, font: &Font, width: Length) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{line} {word}") };
+            if fits(&candidate, font, width) {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            // Hard-break words which do not fit on a line of their own
+            for c in word.chars() {
+                line.push(c);
+                if !fits(&line, font, width) && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::take(&mut line));
+                    line.push(c);
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// `text` unchanged if it is no wider than `width` when rendered in `font`;
+/// otherwise `text` shortened so that it, followed by an ellipsis, is.
+pub fn ellipsize(text: &str, font: &Font, width: Length) -> String {
+    if fits(text, font, width) {
+        return text.to_string();
+    }
+    let mut text = text.trim_end().to_string();
+    loop {
+        let candidate = format!("{text}{ELLIPSIS}");
+        if text.is_empty() || fits(&candidate, font, width) {
+            return candidate;
+        }
+        text.pop();
+        text.truncate(text.trim_end().len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimensions::{payment, receipt};
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case("", 0.0)]
+    #[case("W", 0.944)]
+    #[case("il", 0.444)]
+    #[case("Müller", 0.833 + 0.556 * 2.0 + 0.222 * 2.0 + 0.333)]
+    #[case("Ł", 0.556)] // measured as 'L'
+    #[case("\u{E000}", 1.0)]
+    fn width_in_ems(#[case] text: &str, #[case] expected_ems: f64) {
+        let font = payment().font.value;
+        let expected = font.size.as_uu() * expected_ems;
+        assert!((text_width(text, &font).as_uu() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bold_is_wider() {
+        let font = receipt().font.heading;
+        let normal = receipt().font.value;
+        let normal = Font { size: font.size, ..normal };
+        assert!(text_width("Zahlbar durch", &font).as_uu() > text_width("Zahlbar durch", &normal).as_uu());
+    }
+
+    #[test]
+    fn wide_glyphs_wrap_earlier_than_narrow_ones() {
+        let font = receipt().font.value;
+        let width = receipt().section.information_size.x;
+        let narrow = "il ".repeat(40);
+        let wide = "WM ".repeat(40);
+        assert!(wrap(&wide, &font, width).len() > wrap(&narrow, &font, width).len());
+        for line in wrap(&wide, &font, width) {
+            assert!(fits(&line, &font, width), "{line:?} is too wide");
+        }
+    }
+
+    #[rstest]
+    #[case("Max Muster & Söhne", vec!["Max Muster & Söhne"])]
+    #[case("Zeile 1\nZeile 2", vec!["Zeile 1", "Zeile 2"])]
+    #[case("Wolfgang Maximilian Wilhelm von Mümmelmannsberg-Wolkenkratzer",
+           vec!["Wolfgang Maximilian Wilhelm von", "Mümmelmannsberg-Wolkenkratzer"])]
+    fn wraps_between_words(#[case] text: &str, #[case] expected: Vec<&str>) {
+        let font = receipt().font.value;
+        let width = receipt().section.information_size.x;
+        assert_eq!(wrap(text, &font, width), expected);
+    }
+
+    #[test]
+    fn breaks_words_longer_than_a_line() {
+        let font = receipt().font.value;
+        let width = receipt().section.information_size.x;
+        let word = "W".repeat(60);
+        let lines = wrap(&word, &font, width);
+        assert!(lines.len() > 1);
+        assert_eq!(lines.concat(), word);
+    }
+
+    #[test]
+    fn ellipsize_fits() {
+        let font = payment().font.value;
+        let width = Length::mm(20.0);
+        let shortened = ellipsize("Prôméñądë dès Dïàçrîtiqêß 12", &font, width);
+        assert!(shortened.ends_with(ELLIPSIS));
+        assert!(fits(&shortened, &font, width));
+        assert_eq!(ellipsize("Short", &font, width), "Short");
+        assert_eq!(ellipsize("Short…", &font, width), "Short…");
+    }
+}
@@ -0,0 +1,387 @@
+//! Credit transfer initiations in ISO 20022 pain.001.001.09, as specified by
+//! the Swiss Payment Standards, to pay scanned bills through the bank of the
+//! debtor.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use iban::IbanLike;
+use xmlwriter::{Options, XmlWriter};
+
+use crate::{Address, Iban, QRBill, Reference};
+
+const NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:pain.001.001.09";
+const MAX_ID_CHARS: usize = 35;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("A credit transfer needs at least one bill.")]
+    NoTransfers,
+    #[error("The bill for transfer {0} has no amount, and none was given.")]
+    MissingAmount(usize),
+    #[error("The amount of transfer {0} must be positive, but {1:.2} was given.")]
+    InvalidAmount(usize, f64),
+    #[error("An ID may have between 1 and 35 characters, but '{0}' was given.")]
+    InvalidId(String),
+    #[error("Only banks in CH or LI can be identified by their IID, so a debtor account from {0} needs a BIC.")]
+    MissingBic(String),
+}
+
+/// The account the bills are paid from
+#[derive(Debug, Clone)]
+pub struct Debtor {
+    pub name: String,
+    pub address: Option<Address>,
+    pub account: Iban,
+    /// BIC of the bank of the debtor. Without, the bank is identified by the
+    /// IID of `account`, which must then be from CH or LI.
+    pub bic: Option<String>,
+}
+
+/// The payment of one bill
+pub struct CreditTransfer<'a> {
+    pub bill: &'a QRBill,
+    /// The amount to pay, for bills without one. Overrides the amount of the
+    /// bill if given.
+    pub amount: Option<f64>,
+    /// The ID reported back to the debtor; `NOTPROVIDED` if `None`
+    pub end_to_end_id: Option<String>,
+    /// The final recipient of the payment, if not the creditor of the bill
+    pub ultimate_creditor: Option<Address>,
+}
+
+impl<'a> CreditTransfer<'a> {
+    pub fn new(bill: &'a QRBill) -> Self {
+        Self { bill, amount: None, end_to_end_id: None, ultimate_creditor: None }
+    }
+}
+
+/// Create a pain.001.001.09 document paying `transfers` from the account of
+/// `debtor` on `execution_date`.
+pub fn document(
+    message_id: &str,
+    created: NaiveDateTime,
+    execution_date: NaiveDate,
+    debtor: &Debtor,
+    transfers: &[CreditTransfer],
+) -> Result<String, Error> {
+    check_id(message_id)?;
+    if debtor.bic.is_none() && !crate::IBAN_ALLOWED_COUNTRIES.contains(&debtor.account.country_code()) {
+        return Err(Error::MissingBic(debtor.account.country_code().to_string()));
+    }
+    if transfers.is_empty() {
+        return Err(Error::NoTransfers);
+    }
+    let amounts = transfers
+        .iter()
+        .enumerate()
+        .map(|(i, transfer)| match transfer.amount.or(transfer.bill.amount) {
+            None => Err(Error::MissingAmount(i)),
+            Some(amount) if !(amount.is_finite() && amount > 0.0) => Err(Error::InvalidAmount(i, amount)),
+            Some(amount) => Ok(amount),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for id in transfers.iter().filter_map(|t| t.end_to_end_id.as_deref()) {
+        check_id(id)?;
+    }
+    let number_of_transactions = transfers.len().to_string();
+    let control_sum = format!("{:.2}", amounts.iter().sum::<f64>());
+
+    let mut w = XmlWriter::new(Options::default());
+    w.write_declaration();
+    w.start_element("Document");
+    w.write_attribute("xmlns", NAMESPACE);
+    w.start_element("CstmrCdtTrfInitn");
+
+    w.start_element("GrpHdr");
+    text(&mut w, "MsgId", message_id);
+    text(&mut w, "CreDtTm", &created.format("%Y-%m-%dT%H:%M:%S").to_string());
+    text(&mut w, "NbOfTxs", &number_of_transactions);
+    text(&mut w, "CtrlSum", &control_sum);
+    w.start_element("InitgPty");
+    text(&mut w, "Nm", &debtor.name);
+    w.end_element();
+    w.end_element();
+
+    w.start_element("PmtInf");
+    text(&mut w, "PmtInfId", message_id);
+    text(&mut w, "PmtMtd", "TRF");
+    text(&mut w, "NbOfTxs", &number_of_transactions);
+    text(&mut w, "CtrlSum", &control_sum);
+    w.start_element("ReqdExctnDt");
+    text(&mut w, "Dt", &execution_date.format("%Y-%m-%d").to_string());
+    w.end_element();
+    party(&mut w, "Dbtr", &debtor.name, debtor.address.as_ref());
+    account(&mut w, "DbtrAcct", &debtor.account);
+    w.start_element("DbtrAgt");
+    w.start_element("FinInstnId");
+    match &debtor.bic {
+        Some(bic) => text(&mut w, "BICFI", bic),
+        None => {
+            w.start_element("ClrSysMmbId");
+            w.start_element("ClrSysId");
+            text(&mut w, "Cd", "CHBCC");
+            w.end_element();
+            text(&mut w, "MmbId", &crate::iid(&debtor.account).to_string());
+            w.end_element();
+        }
+    }
+    w.end_element();
+    w.end_element();
+
+    for (i, (transfer, amount)) in transfers.iter().zip(&amounts).enumerate() {
+        let bill = transfer.bill;
+        w.start_element("CdtTrfTxInf");
+        w.start_element("PmtId");
+        text(&mut w, "InstrId", &(i + 1).to_string());
+        text(&mut w, "EndToEndId", transfer.end_to_end_id.as_deref().unwrap_or("NOTPROVIDED"));
+        w.end_element();
+        w.start_element("Amt");
+        w.start_element("InstdAmt");
+        w.write_attribute("Ccy", &bill.currency);
+        w.write_text(&format!("{amount:.2}"));
+        w.end_element();
+        w.end_element();
+        party(&mut w, "Cdtr", bill.creditor.name(), Some(&bill.creditor));
+        account(&mut w, "CdtrAcct", &bill.account);
+        if let Some(ultimate_creditor) = &transfer.ultimate_creditor {
+            party(&mut w, "UltmtCdtr", ultimate_creditor.name(), Some(ultimate_creditor));
+        }
+        remittance_information(&mut w, bill);
+        w.end_element();
+    }
+
+    Ok(w.end_document())
+}
+
+/// The reference, if any, goes into `Strd` together with the message, as
+/// `AddtlRmtInf`; only without a reference is the message sent as `Ustrd`.
+fn remittance_information(w: &mut XmlWriter, bill: &QRBill) {
+    let message = bill.extra_infos.as_deref().filter(|m| !m.is_empty());
+    let reference = match &bill.reference {
+        Reference::Qrr(esr) => Some(("Prtry", "QRR", esr.to_raw())),
+        Reference::Scor(reference) => Some(("Cd", "SCOR", reference.with_checksum())),
+        Reference::None => None,
+    };
+    if message.is_none() && reference.is_none() {
+        return;
+    }
+    w.start_element("RmtInf");
+    match reference {
+        Some((kind, code, reference)) => {
+            w.start_element("Strd");
+            w.start_element("CdtrRefInf");
+            w.start_element("Tp");
+            w.start_element("CdOrPrtry");
+            text(w, kind, code);
+            w.end_element();
+            w.end_element();
+            text(w, "Ref", &reference);
+            w.end_element();
+            if let Some(message) = message {
+                text(w, "AddtlRmtInf", message);
+            }
+            w.end_element();
+        }
+        None => {
+            if let Some(message) = message {
+                text(w, "Ustrd", message);
+            }
+        }
+    }
+    w.end_element();
+}
+
+fn party(w: &mut XmlWriter, element: &str, name: &str, address: Option<&Address>) {
+    w.start_element(element);
+    text(w, "Nm", name);
+    if let Some(address) = address {
+        w.start_element("PstlAdr");
+        match address {
+            Address::Structured(a) => {
+                // Street and house number are optional in a structured address
+                if !a.street.is_empty() {
+                    text(w, "StrtNm", &a.street);
+                }
+                if !a.house_number.is_empty() {
+                    text(w, "BldgNb", &a.house_number);
+                }
+                text(w, "PstCd", &a.postal_code);
+                text(w, "TwnNm", &a.city);
+                text(w, "Ctry", a.country.alpha2());
+            }
+            Address::Cobined(a) => {
+                text(w, "Ctry", a.country.alpha2());
+                text(w, "AdrLine", &a.line1);
+                text(w, "AdrLine", &a.line2);
+            }
+        }
+        w.end_element();
+    }
+    w.end_element();
+}
+
+fn account(w: &mut XmlWriter, element: &str, iban: &Iban) {
+    w.start_element(element);
+    w.start_element("Id");
+    text(w, "IBAN", iban.electronic_str());
+    w.end_element();
+    w.end_element();
+}
+
+fn text(w: &mut XmlWriter, element: &str, text: &str) {
+    w.start_element(element);
+    w.write_text(text);
+    w.end_element();
+}
+
+fn check_id(id: &str) -> Result<(), Error> {
+    match id.chars().count() {
+        1..=MAX_ID_CHARS => Ok(()),
+        _ => Err(Error::InvalidId(id.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{esr::Esr, iso11649::Iso11649, tests::options, QRBillOptions};
+    use pretty_assertions::assert_eq;
+
+    fn bill(reference: Reference, extra_infos: Option<&str>) -> QRBill {
+        QRBill::new(QRBillOptions {
+            reference,
+            account: "CH1430000000800004703".parse().unwrap(),
+            extra_infos: extra_infos.map(str::to_string),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap()
+    }
+
+    fn debtor() -> Debtor {
+        Debtor {
+            name: "Pia Rutschmann".into(),
+            address: None,
+            account: "CH9300762011623852957".parse().unwrap(),
+            bic: None,
+        }
+    }
+
+    fn created() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(10, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn pays_bills() {
+        let qrr = bill(Reference::Qrr(Esr::try_new("210000000003139471430009017".into()).unwrap()), Some("Order 42"));
+        let scor = bill(Reference::Scor(Iso11649::new("539007547034")), None);
+        let mut transfer = CreditTransfer::new(&scor);
+        transfer.amount = Some(100.0);
+        transfer.end_to_end_id = Some("E2E-2".into());
+        let xml = document(
+            "MSG-1",
+            created(),
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+            &debtor(),
+            &[CreditTransfer::new(&qrr), transfer],
+        ).unwrap();
+
+        let expected_parts = [
+            r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pain.001.001.09">"#,
+            "<CreDtTm>2024-03-01T10:30:00</CreDtTm>",
+            "<NbOfTxs>2</NbOfTxs>",
+            "<CtrlSum>2049.75</CtrlSum>",
+            "<Dt>2024-03-05</Dt>",
+            "<MmbId>762</MmbId>",
+            r#"<InstdAmt Ccy="CHF">1949.75</InstdAmt>"#,
+            r#"<InstdAmt Ccy="CHF">100.00</InstdAmt>"#,
+            "<EndToEndId>NOTPROVIDED</EndToEndId>",
+            "<EndToEndId>E2E-2</EndToEndId>",
+            "<IBAN>CH1430000000800004703</IBAN>",
+            "<StrtNm>Musterstrasse</StrtNm>",
+            "<Prtry>QRR</Prtry>",
+            "<Ref>210000000003139471430009017</Ref>",
+            "<AddtlRmtInf>Order 42</AddtlRmtInf>",
+            "<Cd>SCOR</Cd>",
+            "<Ref>RF18539007547034</Ref>",
+        ];
+        let compact: String = xml.lines().map(str::trim).collect();
+        for part in expected_parts {
+            assert!(compact.contains(part), "{part} is missing in\n{xml}");
+        }
+        assert!(!xml.contains("UltmtCdtr"));
+    }
+
+    #[test]
+    fn message_goes_with_the_reference() {
+        let remittance = |reference, extra_infos| {
+            let bill = bill(reference, extra_infos);
+            let xml = document("MSG-1", created(), created().date(), &debtor(), &[CreditTransfer::new(&bill)]).unwrap();
+            let compact: String = xml.lines().map(str::trim).collect();
+            let start = compact.find("<RmtInf>").unwrap();
+            let end = compact.find("</RmtInf>").unwrap() + "</RmtInf>".len();
+            compact[start..end].to_string()
+        };
+        let scor = Reference::Scor(Iso11649::new("539007547034"));
+        assert_eq!(
+            remittance(scor, Some("Order 42")),
+            "<RmtInf><Strd>\
+                <CdtrRefInf><Tp><CdOrPrtry><Cd>SCOR</Cd></CdOrPrtry></Tp><Ref>RF18539007547034</Ref></CdtrRefInf>\
+                <AddtlRmtInf>Order 42</AddtlRmtInf>\
+             </Strd></RmtInf>",
+        );
+        assert_eq!(remittance(Reference::None, Some("Order 42")), "<RmtInf><Ustrd>Order 42</Ustrd></RmtInf>");
+    }
+
+    #[test]
+    fn skips_empty_street_and_house_number() {
+        let mut creditor = crate::tests::address("Muster AG".into());
+        if let Address::Structured(a) = &mut creditor {
+            a.street.clear();
+            a.house_number.clear();
+        }
+        let bill = QRBill::new(QRBillOptions { creditor, ..options("Max Muster".into(), vec![]) }).unwrap();
+        let xml = document("MSG-1", created(), created().date(), &debtor(), &[CreditTransfer::new(&bill)]).unwrap();
+        let compact: String = xml.lines().map(str::trim).collect();
+        assert!(compact.contains("<Cdtr><Nm>Muster AG</Nm><PstlAdr><PstCd>8000</PstCd>"), "{xml}");
+        assert!(!xml.contains("<StrtNm>") && !xml.contains("<BldgNb>"));
+    }
+
+    #[test]
+    fn ultimate_creditor() {
+        let bill = bill(Reference::None, None);
+        let mut transfer = CreditTransfer::new(&bill);
+        transfer.ultimate_creditor = Some(crate::tests::address("Muster AG".into()));
+        let xml = document("MSG-1", created(), created().date(), &debtor(), &[transfer]).unwrap();
+        let compact: String = xml.lines().map(str::trim).collect();
+        assert!(compact.contains("<UltmtCdtr><Nm>Muster AG</Nm>"), "{xml}");
+        assert!(!xml.contains("RmtInf"));
+    }
+
+    #[test]
+    fn rejects() {
+        let bill = bill(Reference::None, None);
+        let mut transfer = CreditTransfer::new(&bill);
+        assert_eq!(document("MSG-1", created(), created().date(), &debtor(), &[]), Err(Error::NoTransfers));
+        assert_eq!(
+            document(&"M".repeat(36), created(), created().date(), &debtor(), &[CreditTransfer::new(&bill)]),
+            Err(Error::InvalidId("M".repeat(36))),
+        );
+        transfer.amount = Some(-1.0);
+        assert_eq!(
+            document("MSG-1", created(), created().date(), &debtor(), &[transfer]),
+            Err(Error::InvalidAmount(0, -1.0)),
+        );
+    }
+
+    #[test]
+    fn foreign_debtor_account_needs_bic() {
+        let bill = bill(Reference::None, None);
+        let mut debtor = Debtor { account: "GB29NWBK60161331926819".parse().unwrap(), ..debtor() };
+        assert_eq!(
+            document("MSG-1", created(), created().date(), &debtor, &[CreditTransfer::new(&bill)]),
+            Err(Error::MissingBic("GB".into())),
+        );
+        debtor.bic = Some("NWBKGB2L".into());
+        let xml = document("MSG-1", created(), created().date(), &debtor, &[CreditTransfer::new(&bill)]).unwrap();
+        let compact: String = xml.lines().map(str::trim).collect();
+        assert!(compact.contains("<DbtrAgt><FinInstnId><BICFI>NWBKGB2L</BICFI></FinInstnId></DbtrAgt>"), "{xml}");
+    }
+}
@@ -0,0 +1,208 @@
+//! Reminders (dunning): a new bill for an unpaid one, with a fee added to its
+//! amount and a note of the reminder level in its additional information.
+
+use chrono::NaiveDate;
+
+use crate::{
+    swico::{PaymentCondition, PaymentConditions, S1},
+    QRBill, QRBillOptions, Reference,
+};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("The bill has no amount to add the fee to.")]
+    NoAmount,
+    #[error("The fee must not be negative, but {0:.2} was given.")]
+    InvalidFee(f64),
+    #[error("The bill only notifies of a payment made by other means.")]
+    Notification,
+}
+
+/// How often the debtor has been reminded, including this reminder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    First,
+    Second,
+    Third,
+}
+
+/// What changes from the original bill to its reminder
+pub struct ReminderOptions {
+    pub level: Level,
+    /// Added to the amount of the original bill
+    pub fee: f64,
+    /// The new due date, if any; otherwise the one of the original bill.
+    /// A new due date replaces the net payment term (`/40/0:days`) of the
+    /// bill information by one up to it.
+    pub due_date: Option<NaiveDate>,
+    /// A reference linked to the original one, if any; otherwise the
+    /// reference of the original bill
+    pub reference: Option<Reference>,
+}
+
+impl ReminderOptions {
+    pub fn new(level: Level, fee: f64) -> Self {
+        Self { level, fee, due_date: None, reference: None }
+    }
+}
+
+impl QRBill {
+    /// Creates a reminder for this bill.
+    ///
+    /// The reminder is the same bill with `options.fee` added to its amount
+    /// and "Reminder 1", "Reminder 2" or "Reminder 3", in the language of the
+    /// bill or as replaced by its `texts`, put in front of its additional
+    /// information. Make every reminder from the original bill, not from a
+    /// previous reminder: the fee and the note would be added twice.
+    ///
+    /// The discounts in the payment conditions of the original bill are
+    /// dropped, as they have expired, and only its net payment term is kept.
+    /// A new due date replaces that term, encoded as net payment term counted
+    /// from the document date: see `S1::with_due_date`.
+    pub fn reminder(&self, options: ReminderOptions) -> Result<Self, crate::Error> {
+        if self.notification {
+            return Err(crate::Error::Reminder(Error::Notification));
+        }
+        if !(options.fee.is_finite() && options.fee >= 0.0) {
+            return Err(crate::Error::Reminder(Error::InvalidFee(options.fee)));
+        }
+        let amount = self.amount.ok_or(crate::Error::Reminder(Error::NoAmount))?;
+        let note = &self.texts.reminder[options.level as usize];
+        let extra_infos = match &self.extra_infos {
+            Some(info) if !info.is_empty() => format!("{note} {info}"),
+            _ => note.to_string(),
+        };
+        Self::create(
+            QRBillOptions {
+                account: self.account,
+                creditor: self.creditor.clone(),
+                amount: Some(crate::swico::round_to_centime(amount + options.fee)),
+                currency: self.currency,
+                due_date: options.due_date.or(self.due_date),
+                debtor: self.debtor.clone(),
+                reference: options.reference.unwrap_or_else(|| self.reference.clone()),
+                extra_infos: Some(extra_infos),
+                // Discounts have expired, and a new due date replaces the net term
+                bill_information: self.bill_information.clone().map(|s1| S1 {
+                    conditions: match options.due_date {
+                        Some(_) => None,
+                        None => s1.conditions.as_ref().and_then(PaymentConditions::net_days).map(|days| {
+                            PaymentConditions(vec![PaymentCondition { discount: 0.0, days }])
+                        }),
+                    },
+                    ..s1
+                }),
+                alternative_processes: self.alternative_processes.clone(),
+                language: self.language,
+                texts: Some(self.texts.clone()),
+                font_size: self.font_size,
+                top_line: self.line_top,
+                separate_note: self.separate_note,
+                payment_line: self.line_mid,
+                epc_code: self.epc_code,
+                due_date_heading: self.due_date_heading,
+                svg_id: None,
+            },
+            false,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iso11649::Iso11649, tests::options, Language};
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    fn bill(language: Language, extra_infos: Option<&str>) -> QRBill {
+        QRBill::new(QRBillOptions {
+            language,
+            extra_infos: extra_infos.map(str::to_string),
+            reference: Reference::Scor(Iso11649::new("4567")),
+            due_date: NaiveDate::from_ymd_opt(2024, 5, 31),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap()
+    }
+
+    #[rstest]
+    #[case(Language::English, Level::First , None             , "Reminder 1")]
+    #[case(Language::German , Level::Second, Some("Order 42") , "2. Mahnung Order 42")]
+    #[case(Language::French , Level::First , Some("")         , "1er rappel")]
+    #[case(Language::Italian, Level::Third , Some("Ordine 42"), "3° sollecito Ordine 42")]
+    fn reminder(#[case] language: Language, #[case] level: Level, #[case] extra_infos: Option<&str>, #[case] message: &str) {
+        let original = bill(language, extra_infos);
+        let reminder = original.reminder(ReminderOptions::new(level, 20.0)).unwrap();
+        let data = reminder.qr_data();
+        let lines: Vec<_> = data.lines().collect();
+        assert_eq!(lines[18], "1969.75");
+        assert_eq!(lines[27..31], ["SCOR", "RF794567", message, "EPD"]);
+        assert_eq!(reminder.due_date, original.due_date);
+    }
+
+    #[test]
+    fn new_due_date_and_reference() {
+        let due_date = NaiveDate::from_ymd_opt(2024, 6, 30);
+        let reminder = bill(Language::English, None).reminder(ReminderOptions {
+            due_date,
+            reference: Some(Reference::Scor(Iso11649::new("4567R1"))),
+            ..ReminderOptions::new(Level::First, 0.0)
+        }).unwrap();
+        assert_eq!(reminder.due_date, due_date);
+        assert_eq!(reminder.qr_data().lines().nth(28), Some(Iso11649::new("4567R1").with_checksum().as_str()));
+        assert_eq!(reminder.amount, Some(1949.75));
+    }
+
+    #[test]
+    fn new_due_date_in_bill_information() {
+        let original = QRBill::new(QRBillOptions {
+            bill_information: Some("//S1/10/42/11/240501/40/2:10;0:30".parse().unwrap()),
+            due_date: NaiveDate::from_ymd_opt(2024, 5, 31),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let reminder = original.reminder(ReminderOptions {
+            due_date: NaiveDate::from_ymd_opt(2024, 6, 30),
+            ..ReminderOptions::new(Level::First, 20.0)
+        }).unwrap();
+        assert_eq!(reminder.qr_data().lines().nth(31), Some("//S1/10/42/11/240501/40/0:60"));
+    }
+
+    #[rstest]
+    #[case("//S1/11/240301/40/2:10;0:30", "//S1/11/240301/40/0:30")]
+    #[case("//S1/11/240301/40/2:10"     , "//S1/11/240301"        )]
+    fn discounts_are_dropped(#[case] bill_information: &str, #[case] expected: &str) {
+        let original = QRBill::new(QRBillOptions {
+            bill_information: Some(bill_information.parse().unwrap()),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let reminder = original.reminder(ReminderOptions::new(Level::First, 20.0)).unwrap();
+        assert_eq!(reminder.qr_data().lines().nth(31), Some(expected));
+        assert_eq!(reminder.due_date, original.due_date);
+    }
+
+    #[test]
+    fn replaced_note() {
+        let mut texts = crate::label::Texts::for_language(Language::German);
+        texts.reminder[0] = "1. Mahnig".into();
+        let original = QRBill::new(QRBillOptions {
+            language: Language::German,
+            texts: Some(texts),
+            ..options("Max Muster".into(), vec![])
+        }).unwrap();
+        let reminder = original.reminder(ReminderOptions::new(Level::First, 0.0)).unwrap();
+        assert_eq!(reminder.qr_data().lines().nth(29), Some("1. Mahnig"));
+    }
+
+    #[test]
+    fn rejects() {
+        let error = |bill: &QRBill, fee| match bill.reminder(ReminderOptions::new(Level::First, fee)) {
+            Err(crate::Error::Reminder(error)) => Some(error),
+            _ => None,
+        };
+        let no_amount = QRBill::new(QRBillOptions { amount: None, ..options("Max Muster".into(), vec![]) }).unwrap();
+        let notification = QRBill::notification(options("Max Muster".into(), vec![])).unwrap();
+        assert_eq!(error(&no_amount, 20.0), Some(Error::NoAmount));
+        assert_eq!(error(&bill(Language::English, None), -1.0), Some(Error::InvalidFee(-1.0)));
+        assert_eq!(error(&notification, 20.0), Some(Error::Notification));
+    }
+}
@@ -1,10 +1,13 @@
 use crate::{
-    Group, Error, Line, Path,
+    ClassExt, Group, Error, Line, Path, Text,
     mm,
     render,
     A4_WIDTH,
 };
 
+/// Distance of the baseline of the note above the top line, in mm
+const SEPARATE_NOTE_OFFSET: f64 = 2.0;
+
 pub const SCISSORS_SVG_PATH: &str = "m 0.764814,4.283977 c 0.337358,0.143009 0.862476,-0.115279 0.775145,-0.523225 -0.145918,-0.497473
     -0.970289,-0.497475 -1.116209,-2e-6 -0.0636,0.23988 0.128719,0.447618 0.341064,0.523227 z m 3.875732,-1.917196
     c 1.069702,0.434082 2.139405,0.868164 3.209107,1.302246 -0.295734,0.396158 -0.866482,0.368049 -1.293405,0.239509
@@ -85,4 +88,14 @@ impl crate::QRBill {
         Ok(group)
     }
 
+    /// "Separate before paying in" above the middle of the top line
+    pub fn separate_note(&self) -> Text {
+        Text::new("")
+            .add(svg::node::Text::new(self.texts.separate_before_paying_in.as_str()))
+            .set("x", A4_WIDTH / 2.0)
+            .set("y", -mm(SEPARATE_NOTE_OFFSET))
+            .set("text-anchor", "middle")
+            .class("separate-note")
+    }
+
 }
@@ -1,8 +1,8 @@
 use chrono::NaiveDate;
 
 use crate::{
-    dimensions::{self as dims, Dimensions, Xy, payment, receipt},
-    format_amount, label, AddressExt, Group, Language, Line, QRBill, Reference, ClassExt, Text, Error,
+    dimensions::{self as dims, Dimensions, Length, Xy, FontSize, payment_with_font_size, receipt},
+    format_amount, label, metrics, AddressExt, Group, Language, Line, QRBill, Reference, ClassExt, Text, Error,
 };
 
 pub mod cut;
@@ -34,15 +34,56 @@ impl Render {
             What::ReceiptAndPayment => vec![Part::Receipt, Part::Payment],
         };
         for part in parts {
-            group = group.add(Self::new(part, bill.language).render_all(bill)?);
+            group = group.add(Self::for_bill(part, bill).render_all(bill)?);
         }
         Ok(group)
     }
 
+    /// The CSS for the fonts which `Render::bill` will use for `bill`
+    pub fn styles(bill: &QRBill) -> String {
+        let receipt = Self::for_bill(Part::Receipt, bill);
+        let payment = Self::for_bill(Part::Payment, bill);
+        dims::make_svg_styles(&receipt.dims.font, &payment.dims.font)
+    }
+
     pub fn new(part: Part, language: Language) -> Self {
-        let (dims, classes) = match part {
-            Part::Receipt => (receipt(), PartStyleClasses::receipt()),
-            Part::Payment => (payment(), PartStyleClasses::payment()),
+        let dims = match part {
+            Part::Receipt => receipt(),
+            Part::Payment => dims::payment(),
+        };
+        Self::with_dimensions(part, language, dims)
+    }
+
+    /// Prepare to render `part` of `bill` with the largest permitted font size
+    /// at which the whole information section fits.
+    ///
+    /// Text is wrapped to the width of its section, measured with the metrics
+    /// of the font (see `metrics`). Should the information section still be
+    /// too tall:
+    ///
+    /// 1. the payment part's values are made smaller than the bill's
+    ///    `font_size`, in steps of 0.5 pt, down to `FontSize::SMALLEST`. The
+    ///    font sizes on the receipt are fixed.
+    ///
+    /// 2. the block with the most lines loses its last line, and its new last
+    ///    line is shortened to end in an ellipsis, until everything fits.
+    pub fn for_bill(part: Part, bill: &QRBill) -> Self {
+        if part == Part::Receipt {
+            return Self::new(part, bill.language);
+        }
+        let mut size = bill.font_size;
+        let mut render = Self::with_dimensions(part, bill.language, payment_with_font_size(size));
+        while size > FontSize::SMALLEST && !render.fits(&render.information(bill)) {
+            size = FontSize::new(size.value_in_pt() - 0.5).unwrap_or(FontSize::SMALLEST);
+            render = Self::with_dimensions(part, bill.language, payment_with_font_size(size));
+        }
+        render
+    }
+
+    fn with_dimensions(part: Part, language: Language, dims: Dimensions) -> Self {
+        let classes = match part {
+            Part::Receipt => PartStyleClasses::receipt(),
+            Part::Payment => PartStyleClasses::payment(),
         };
         let label = label::Labels::for_language(language);
         macro_rules! sty { ($a:ident) => {                    Style { class: classes.$a,          text_size: dims.font.$a          }  }; }
@@ -84,60 +125,122 @@ impl Render {
         }
     }
 
-    fn section_information(&self, bill: &QRBill) -> Group {
-        let Self { dims, label, sty, .. } = self;
-
-        let mut g = Group::new();
-        let mut cursor = dims.section.information;
-        macro_rules! skip_one_line { () => (g = g.add(txt(&mut cursor, &sty.value, ""))); }
-
-        // ----- Account / Payable to ------------------------------------------
-        g = g
-            .add(txt(&mut cursor, &sty.heading, label.payable_to))
-            .add(txt(&mut cursor, &sty.value  , format!("{}", bill.account)));
-
-        for line in bill.creditor.as_paragraph(dims.max_chars_line) {
-            g = g.add(txt(&mut cursor, &sty.value, line));
+    /// The contents of the information section, wrapped to its width
+    fn information<'a>(&self, bill: &'a QRBill) -> Vec<Block<'a>> {
+        let Self { label, sty, .. } = self;
+        let width = self.dims.section.information_size.x;
+        let wrap = |lines: Vec<String>| Content::Lines(
+            lines.iter()
+                .flat_map(|line| metrics::wrap(line, &sty.value.text_size, width))
+                .collect()
+        );
+        let mut blocks = vec![];
+
+        // ----- Notification --------------------------------------------------
+        // Heading only, so that it stands out in bold on both parts
+        if bill.notification {
+            blocks.push(Block { heading: &bill.texts.do_not_use_for_payment, content: Content::Lines(vec![]) });
         }
-        skip_one_line!();
+        // ----- Account / Payable to ------------------------------------------
+        let mut lines = vec![format!("{}", bill.account)];
+        lines.extend(bill.creditor.as_paragraph());
+        blocks.push(Block { heading: label.payable_to, content: wrap(lines) });
         // ----- Reference -----------------------------------------------------
         if !matches!(bill.reference, Reference::None) {
-            g = g.add(txt(&mut cursor, &sty.heading,              label.reference))
-                 .add(txt(&mut cursor, &sty.value  , format!("{}", bill.reference)));
-            skip_one_line!();
+            blocks.push(Block { heading: label.reference, content: wrap(vec![format!("{}", bill.reference)]) });
         }
         // ----- Additional Information ----------------------------------------
-        if let (Part::Payment, Some(info)) = (self.part, &bill.extra_infos) {
-            g = g.add(txt(&mut cursor, &sty.heading, label.additional_information));
-            // TODO cheating on additional information content: see Ustrd and StrdBkginf in spec
-            for line in info.lines() {
-                g = g.add(txt(&mut cursor, &sty.value, line));
-            }
-            skip_one_line!();
+        // The unstructured message, then the bill information
+        let lines: Vec<String> = bill.extra_infos.iter()
+            .flat_map(|info| info.lines().map(String::from))
+            .chain(bill.bill_information.iter().map(|s1| s1.to_string()))
+            .collect();
+        if self.part == Part::Payment && !lines.is_empty() {
+            blocks.push(Block { heading: label.additional_information, content: wrap(lines) });
         }
         // ----- Due date ------------------------------------------------------
-        // Can't find anything about due date in the standard! Is it some
-        // specific kind of additional information that exists only in this
-        // crate?
-        if let Some(date) = bill.due_date {
-            g = g.add(txt(&mut cursor, &sty.heading, label.payable_by_date))
-                 .add(txt(&mut cursor, &sty.value  , format_date(date)));
-            skip_one_line!();
+        // Not part of the standard, which only knows the due date as part of
+        // the bill information, so it can be left out.
+        if let (true, Some(date)) = (bill.due_date_heading, bill.due_date) {
+            blocks.push(Block { heading: &bill.texts.payable_by_date, content: wrap(vec![format_date(date)]) });
         }
         // ----- Debtor --------------------------------------------------------
         if let Some(debtor) = &bill.debtor {
-            g = g.add(txt(&mut cursor, &sty.heading, label.payable_by));
-            for line in debtor.as_paragraph(dims.max_chars_line) {
-                g = g.add(txt(&mut cursor, &sty.value, line));
-            }
+            blocks.push(Block { heading: label.payable_by, content: wrap(debtor.as_paragraph()) });
         } else {
-            g = g.add(txt(&mut cursor, &sty.heading, label.payable_by_extended));
-            /*TODO why do we need this hack? */cursor.y += dims::Length::mm(1.5);
-            let (Xy { x, y }, Xy { x: w, y: h }) = (cursor, dims.blank_payable);
-            g = g.add(self.blank_rect(x.as_uu(), y.as_uu(), w.as_uu(), h.as_uu()));
+            blocks.push(Block { heading: label.payable_by_extended, content: Content::Blank });
+        }
+        blocks
+    }
+
+    /// The vertical space taken up by `blocks` in the information section
+    fn height(&self, blocks: &[Block]) -> Length {
+        let Self { dims, sty, .. } = self;
+        let heading = sty.heading.text_size.line_spacing.as_uu();
+        let value   = sty.value  .text_size.line_spacing.as_uu();
+        let blank   = blank_payable_offset().as_uu() + dims.blank_payable.y.as_uu();
+        let content: f64 = blocks.iter()
+            .map(|block| heading + match &block.content {
+                Content::Lines(lines) => value * lines.len() as f64,
+                Content::Blank        => blank,
+            })
+            .sum();
+        // One empty line between consecutive blocks
+        let gaps = value * blocks.len().saturating_sub(1) as f64;
+        Length::uu(content + gaps)
+    }
+
+    fn fits(&self, blocks: &[Block]) -> bool {
+        self.height(blocks).as_uu() <= self.dims.section.information_size.y.as_uu()
+    }
+
+    /// Shorten `blocks` until they fit into the information section, by
+    /// repeatedly removing the last line of the block with the most lines.
+    fn truncate<'a>(&self, mut blocks: Vec<Block<'a>>) -> Vec<Block<'a>> {
+        let font = &self.sty.value.text_size;
+        let width = self.dims.section.information_size.x;
+        while !self.fits(&blocks) {
+            let longest = blocks.iter_mut()
+                .filter_map(|block| match &mut block.content {
+                    Content::Lines(lines) if lines.len() > 1 => Some(lines),
+                    _ => None,
+                })
+                .max_by_key(|lines| lines.len());
+            let Some(lines) = longest else { break };
+            lines.pop();
+            // The ellipsis marks the line as cut off, even if it would fit
+            // without it.
+            let last = lines.last_mut().unwrap();
+            let cut = format!("{}{}", last.trim_end_matches(metrics::ELLIPSIS).trim_end(), metrics::ELLIPSIS);
+            *last = metrics::ellipsize(&cut, font, width);
+        }
+        blocks
+    }
+
+    fn section_information(&self, bill: &QRBill) -> Group {
+        let Self { dims, sty, .. } = self;
+
+        let mut g = Group::new();
+        let mut cursor = dims.section.information;
+        macro_rules! skip_one_line { () => (g = g.add(txt(&mut cursor, &sty.value, ""))); }
+
+        let blocks = self.truncate(self.information(bill));
+        let n_blocks = blocks.len();
+        for (i, Block { heading, content }) in blocks.into_iter().enumerate() {
+            g = g.add(txt(&mut cursor, &sty.heading, heading));
+            match content {
+                Content::Lines(lines) => for line in lines {
+                    g = g.add(txt(&mut cursor, &sty.value, line));
+                },
+                Content::Blank => {
+                    /*TODO why do we need this hack? */cursor.y += blank_payable_offset();
+                    let (Xy { x, y }, Xy { x: w, y: h }) = (cursor, dims.blank_payable);
+                    g = g.add(self.blank_rect(x.as_uu(), y.as_uu(), w.as_uu(), h.as_uu()));
+                },
+            }
+            if i + 1 < n_blocks { skip_one_line!(); }
         }
         g
-        // No need to skip_one_line at end
     }
 
     fn section_amount(&self, bill: &QRBill) -> Group {
@@ -242,7 +345,9 @@ struct PartStyleClasses {
     heading:              &'static str,
     value:                &'static str,
     acceptance_pt: Option<&'static str>,
+    #[allow(dead_code)] // TODO implement alternative processes
     alt_proc_bold: Option<&'static str>,
+    #[allow(dead_code)] // TODO implement alternative processes
     alt_proc:      Option<&'static str>,
 }
 
@@ -288,6 +393,22 @@ struct Styles {
     // TODO alternatie processes
 }
 
+/// A heading in the information section together with what follows it
+struct Block<'a> {
+    heading: &'a str,
+    content: Content,
+}
+
+enum Content {
+    /// Values, each of which fits on one line
+    Lines(Vec<String>),
+    /// A blank rectangle for the debtor to fill in by hand
+    Blank,
+}
+
+/// Space between the "Payable by (name/address)" heading and its blank rectangle
+fn blank_payable_offset() -> Length { Length::mm(1.5) }
+
 /// Which parts of the QRBill should be rendered
 pub enum What { OnlyReceipt, OnlyPayment, ReceiptAndPayment  }
 
@@ -308,3 +429,100 @@ fn txt(cursor: &mut Xy, style: &Style, text: impl Into<String>) -> Text {
 fn format_date(date: NaiveDate) -> String {
     date.format("%d.%m.%Y").to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, Currency, QRBillOptions, StructuredAddress};
+    use rstest::*;
+
+    fn address(name: &str) -> Address {
+        Address::Structured(StructuredAddress {
+            name:         name.into(),
+            street:       "Wolkenkratzerweg".into(),
+            house_number: "1".into(),
+            postal_code:  "8000".into(),
+            city:         "Zürich".into(),
+            country:      isocountry::CountryCode::CHE,
+        })
+    }
+
+    fn bill(name: &str, extra_infos: Option<String>, reference: Reference, font_size: FontSize) -> QRBill {
+        QRBill::new(QRBillOptions {
+            account: "CH0300700110000123456".parse().unwrap(),
+            creditor: address(name),
+            amount: Some(100.0),
+            currency: Currency::SwissFranc,
+            due_date: None,
+            debtor: Some(address(name)),
+            reference,
+            extra_infos,
+            bill_information: None,
+            alternative_processes: vec![],
+            language: Language::German,
+            texts: None,
+            font_size,
+            top_line: true,
+            separate_note: true,
+            payment_line: true,
+            epc_code: None,
+            due_date_heading: true,
+            svg_id: None,
+        }).unwrap()
+    }
+
+    fn value_size(render: &Render) -> f64 { render.sty.value.text_size.size.as_pt() }
+
+    #[test]
+    fn short_content_uses_recommended_size() {
+        let render = Render::for_bill(Part::Payment, &bill("Max Muster", None, Reference::None, FontSize::RECOMMENDED));
+        assert!((value_size(&render) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn configured_size_is_used_and_styled() {
+        let bill = bill("Max Muster", None, Reference::None, FontSize::new(9.0).unwrap());
+        let render = Render::for_bill(Part::Payment, &bill);
+        assert!((value_size(&render) - 9.0).abs() < 1e-9);
+        assert!((render.sty.heading.text_size.size.as_pt() - 7.0).abs() < 1e-9);
+        assert!((render.sty.value.text_size.line_spacing.as_pt() - 10.0).abs() < 1e-9);
+        let css = Render::styles(&bill);
+        assert!(css.contains(".p-heading       { font-size: 7pt;"), "{css}");
+        assert!(css.contains(".p-value         { font-size: 9pt;"), "{css}");
+    }
+
+    #[rstest]
+    #[case(7.9, false)]
+    #[case(8.0, true)]
+    #[case(9.5, true)]
+    #[case(10.0, true)]
+    #[case(10.5, false)]
+    #[case(f64::NAN, false)]
+    fn font_size_bounds(#[case] value_in_pt: f64, #[case] valid: bool) {
+        assert_eq!(FontSize::new(value_in_pt).is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case(Part::Receipt, 8.0)]
+    #[case(Part::Payment, 8.0)]
+    fn long_content_is_shrunk_then_truncated(#[case] part: Part, #[case] expected_size: f64) {
+        let name = "W".repeat(70);
+        let reference = Reference::Scor(crate::iso11649::Iso11649::new("WWWWWWWWWWWWWWWWWWWWW"));
+        let bill = bill(&name, Some("W\n".repeat(60)), reference, FontSize::RECOMMENDED);
+        let render = Render::for_bill(part, &bill);
+        assert!((value_size(&render) - expected_size).abs() < 1e-9);
+
+        let blocks = render.truncate(render.information(&bill));
+        assert!(render.fits(&blocks));
+        let width = render.dims.section.information_size.x;
+        for block in &blocks {
+            if let Content::Lines(lines) = &block.content {
+                for line in lines {
+                    assert!(metrics::text_width(line, &render.sty.value.text_size).as_uu() <= width.as_uu());
+                }
+            }
+        }
+        assert!(blocks.iter().any(|block| matches!(&block.content,
+            Content::Lines(lines) if lines.last().unwrap().ends_with(metrics::ELLIPSIS))));
+    }
+}
@@ -1,106 +1,195 @@
-use regex::Regex;
+use svg::{node::element::path::Data, Document};
 
 use crate::{
+    dimensions::{self, Length, Xy},
     Group, Error, Path, QRBill, QrCode, Polygon, Rectangle,
     mm,
 };
 
+/// The Swiss QR Code measures 46 x 46 mm, without its quiet zone.
+fn qr_size() -> Length { Length::mm(46.0) }
+
+/// Width, in modules, of the light margin which ISO/IEC 18004 requires around a
+/// QR code. The 5 mm of free space around the Swiss QR Code is always wider.
+pub const QUIET_ZONE_MODULES: usize = 4;
+
+/// Size of a single module in the standalone image produced by `qr_image`
+const IMAGE_PIXELS_PER_MODULE: usize = 8;
+
 impl QRBill {
 
     pub fn section_qr(&self) -> Result<Group, Error> {
-        let x_lhs = mm(5.0);
-        let x_mid = crate::RECEIPT_WIDTH + x_lhs;
-
-        let path_re = Regex::new(r"<path [^>]*>").unwrap();
-        let data_re = Regex::new(r#" d="([^"]*)""#).unwrap();
-        let size_re = Regex::new(r#"<svg .* width="(\d*)" [^>]*>"#).unwrap();
-
-        let qr_image = self.qr_image()?;
-
-        let size = size_re
-            .captures_iter(&qr_image)
-            .next()
-            .expect("This is a bug. Please report it.");
-
-        let path = path_re
-            .captures_iter(&qr_image)
-            .next()
-            .expect("This is a bug. Please report it.");
-
-        let data = data_re
-            .captures_iter(&path[0])
-            .next()
-            .expect("This is a bug. Please report it.");
-
-        let qr_left = x_mid;
-        let qr_top = 60.0;
-        let scale_factor = mm(45.8)
-            / size[1]
-            .parse::<f64>()
-            .expect("This is a bug. Please report it.");
-
-        let mut group = Group::new();
-        group = group.add(
-            Path::new()
-                .set("d", &data[1])
-                .set(
-                    "style",
-                    "fill:black; fill-opacity:1; fill-rule:nonzero; stroke:none; margin: 0",
-                )
-                .set(
-                    "transform",
-                    format!("translate({}, {}) scale({})", qr_left, qr_top, scale_factor),
-                ),
-        );
-
-        group = group.add(Self::draw_swiss_cross(x_mid, 60.0, mm(45.8)));
-        Ok(group)
+        let Xy { x, y } = dimensions::payment().section.qr_code
+            .expect("The payment part always has a QR code section");
+        let (qr_left, qr_top) = (x.as_uu(), y.as_uu());
+
+        let code = self.qr_code()?;
+        let size = qr_size().as_uu();
+        let module = size / code.width() as f64;
+        let quiet = module * QUIET_ZONE_MODULES as f64;
+
+        Ok(Group::new()
+            .add(
+                Rectangle::new()
+                    .set("x", qr_left - quiet)
+                    .set("y", qr_top - quiet)
+                    .set("width", size + 2.0 * quiet)
+                    .set("height", size + 2.0 * quiet)
+                    .set("fill", "white"),
+            )
+            .add(
+                modules_path(&code)
+                    .set("transform", format!("translate({qr_left}, {qr_top}) scale({module})")),
+            )
+            .add(self.swiss_cross(qr_left, qr_top, size)))
+    }
+
+    /// The EPC QR code for SEPA credit transfers, on a full page at
+    /// `position`: as large as the Swiss QR Code.
+    pub fn section_epc(&self, position: crate::epc::Position) -> Result<Group, Error> {
+        let code = crate::epc::Epc::try_from(self).map_err(Error::Epc)?.qr_code()?;
+        let size = qr_size().as_uu();
+        let (left, top) = (mm(position.x), mm(position.y));
+        let module = size / code.width() as f64;
+        let quiet = module * QUIET_ZONE_MODULES as f64;
+
+        Ok(Group::new()
+            .add(
+                Rectangle::new()
+                    .set("x", left - quiet)
+                    .set("y", top - quiet)
+                    .set("width", size + 2.0 * quiet)
+                    .set("height", size + 2.0 * quiet)
+                    .set("fill", "white"),
+            )
+            .add(
+                modules_path(&code)
+                    .set("transform", format!("translate({left}, {top}) scale({module})")),
+            )
+            .set("id", format!("epc-code-{}", self.id)))
     }
 
-    /// Generate the QR image in string form.
+    /// The QR code encoding `qr_data`, for drawing with backends other than
+    /// SVG.
+    pub fn qr_code(&self) -> Result<QrCode, Error> {
+        Ok(QrCode::with_error_correction_level(self.qr_data(), qrcode::EcLevel::M)?)
+    }
+
+    /// Generate the QR image, including its quiet zone but without the Swiss
+    /// cross, as a standalone SVG document in string form.
     pub fn qr_image(&self) -> Result<String, Error> {
-        let code = QrCode::with_error_correction_level(self.qr_data(), qrcode::EcLevel::M)?;
-        Ok(code
-           .render()
-           .dark_color(qrcode::render::svg::Color("black"))
-           .light_color(qrcode::render::svg::Color("white"))
-           .quiet_zone(false)
-           .build())
+        let code = self.qr_code()?;
+        let modules = code.width() + 2 * QUIET_ZONE_MODULES;
+        let pixels = modules * IMAGE_PIXELS_PER_MODULE;
+        let offset = QUIET_ZONE_MODULES;
+        Ok(Document::new()
+           .set("width", pixels)
+           .set("height", pixels)
+           .set("viewBox", format!("0 0 {modules} {modules}"))
+           .add(
+               Rectangle::new()
+                   .set("width", "100%")
+                   .set("height", "100%")
+                   .set("fill", "white"),
+           )
+           .add(modules_path(&code).set("transform", format!("translate({offset}, {offset})")))
+           .to_string())
     }
 
-    /// Draw the swiss cross in the middle of the QR code.
+    /// Draw the Swiss cross in the middle of the QR code, whose top left
+    /// corner is at `x`, `y` and which is `size` wide.
+    ///
+    /// The logo measures 7 x 7 mm: a black square with a 0.5 mm white border,
+    /// carrying a white cross with the proportions of the Swiss coat of arms
+    /// (see `swiss_cross_points`).
+    ///
+    /// The group has the id `swiss-cross`, the same for every bill. Bills
+    /// draw it with an id of their own: see `QRBillOptions::svg_id`.
     pub fn draw_swiss_cross(x: f64, y: f64, size: f64) -> Group {
-        let scale_factor = mm(7.0) / 19.0;
+        let logo = mm(SWISS_CROSS_LOGO_MM);
+        let border = mm(SWISS_CROSS_BORDER_MM);
+        let points = swiss_cross_points()
+            .iter()
+            .map(|(px, py)| format!("{},{}", mm(*px), mm(*py)))
+            .collect::<Vec<_>>()
+            .join(" ");
         Group::new()
-            .add(
-                Polygon::new()
-                    .set("points", "18.3,0.7 1.6,0.7 0.7,0.7 0.7,1.6 0.7,18.3 0.7,19.1 1.6,19.1 18.3,19.1 19.1,19.1 19.1,18.3 19.1,1.6 19.1,0.7")
-                    .set("fill", "black")
-            )
             .add(
                 Rectangle::new()
-                    .set("x", 8.3)
-                    .set("y", 4.0)
-                    .set("width", 3.3)
-                    .set("height", 11.0)
+                    .set("width", logo)
+                    .set("height", logo)
                     .set("fill", "white")
             )
             .add(
                 Rectangle::new()
-                    .set("x", 4.4)
-                    .set("y", 7.9)
-                    .set("width", 11.0)
-                    .set("height", 3.3)
-                    .set("fill", "white")
+                    .set("x", border)
+                    .set("y", border)
+                    .set("width", logo - 2.0 * border)
+                    .set("height", logo - 2.0 * border)
+                    .set("fill", "black")
             )
             .add(
                 Polygon::new()
-                    .set("points", "0.7,1.6 0.7,18.3 0.7,19.1 1.6,19.1 18.3,19.1 19.1,19.1 19.1,18.3 19.1,1.6 19.1,0.7 18.3,0.7 1.6,0.7 0.7,0.7")
-                    .set("fill", "none")
-                    .set("stroke", "white")
-                    .set("stroke_width", 1.4357)
+                    .set("points", points)
+                    .set("fill", "white")
             )
-            .set("transform", format!("translate({}, {}) scale({})", x + size / 2.0 - 10.0 * scale_factor, y + size / 2.0 - 10.0 * scale_factor, scale_factor))
+            .set("transform", format!("translate({}, {})", x + (size - logo) / 2.0, y + (size - logo) / 2.0))
             .set("id", "swiss-cross")
     }
+
+    /// The Swiss cross of this bill, with an id of its own
+    fn swiss_cross(&self, x: f64, y: f64, size: f64) -> Group {
+        Self::draw_swiss_cross(x, y, size).set("id", format!("swiss-cross-{}", self.id))
+    }
+}
+
+/// The dark modules of `code` as a single SVG path, one module being one unit
+/// wide. Horizontally adjacent dark modules are merged into a single rectangle.
+fn modules_path(code: &QrCode) -> Path {
+    let width = code.width();
+    let colors = code.to_colors();
+    let mut data = Data::new();
+    for (y, row) in colors.chunks(width).enumerate() {
+        let mut x = 0;
+        while x < width {
+            if row[x] != qrcode::Color::Dark {
+                x += 1;
+                continue;
+            }
+            let run = row[x..].iter().take_while(|&&c| c == qrcode::Color::Dark).count();
+            data = data
+                .move_to((x, y))
+                .horizontal_line_by(run)
+                .vertical_line_by(1)
+                .horizontal_line_by(-(run as i64))
+                .close();
+            x += run;
+        }
+    }
+    Path::new()
+        .set("d", data)
+        .set("fill", "black")
+        .set("shape-rendering", "crispEdges")
+}
+
+/// Side length of the Swiss cross logo, including its white border
+const SWISS_CROSS_LOGO_MM: f64 = 7.0;
+
+/// Width of the white border around the black square of the logo
+const SWISS_CROSS_BORDER_MM: f64 = 0.5;
+
+/// Corners, in mm relative to the top left of the logo, of the white cross.
+///
+/// The cross is placed in the black square like the cross on the Swiss flag:
+/// dividing the square into 32 x 32 units, each arm is 6 units wide and 7 units
+/// long, so that the whole cross spans 20 units, centred in the square.
+fn swiss_cross_points() -> [(f64, f64); 12] {
+    let unit = (SWISS_CROSS_LOGO_MM - 2.0 * SWISS_CROSS_BORDER_MM) / 32.0;
+    let centre = SWISS_CROSS_LOGO_MM / 2.0;
+    let (a, b) = (centre -  3.0 * unit, centre +  3.0 * unit); // edges of the arms
+    let (c, d) = (centre - 10.0 * unit, centre + 10.0 * unit); // ends of the arms
+    [
+        (a, c), (b, c), (b, a), (d, a), (d, b), (b, b),
+        (b, d), (a, d), (a, b), (c, b), (c, a), (a, a),
+    ]
 }
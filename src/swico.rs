@@ -0,0 +1,584 @@
+//! Structured bill information following the Swico syntax definition (S1),
+//! e.g. `//S1/10/10201409/11/190512/30/106017086/40/2:10;0:30`.
+
+use chrono::{Duration, NaiveDate};
+
+use crate::uid::{self, Uid};
+
+const PREFIX: &str = "//S1";
+const DATE_FORMAT: &str = "%y%m%d";
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("Swico bill information must start with '//S1'.")]
+    InvalidPrefix,
+    #[error("The tag '{0}' has no value.")]
+    MissingValue(String),
+    #[error("The tag '{0}' appears more than once.")]
+    DuplicateTag(String),
+    #[error("A date must be given as YYMMDD, but '{0}' was given.")]
+    InvalidDate(String),
+    #[error("The UID is invalid: {0}")]
+    InvalidUid(uid::Error),
+    #[error("A payment condition must look like 2:10 (percent:days), but '{0}' was given.")]
+    InvalidCondition(String),
+    #[error("A VAT rate and amount must look like 8:1000 (percent:amount), but '{0}' was given.")]
+    InvalidVatAmount(String),
+    #[error("The net amounts plus VAT add up to {expected:.2}, but the bill is over {found:.2}.")]
+    AmountMismatch { expected: f64, found: f64 },
+    #[error("A due date can only be given with the document date (/11/) it is counted from.")]
+    MissingDocumentDate,
+    #[error("The due date {0} is before the document date.")]
+    DueDateBeforeDocumentDate(NaiveDate),
+    #[error("The due date is {expected}, but the payment conditions make it {found}.")]
+    DueDateMismatch { expected: NaiveDate, found: NaiveDate },
+}
+
+/// The Swico S1 bill information. All parts are optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct S1 {
+    /// `/10/`: Invoice number
+    pub invoice_number: Option<String>,
+    /// `/11/`: Invoice date, which payment conditions count from
+    pub document_date: Option<NaiveDate>,
+    /// `/20/`: Customer reference
+    pub customer_reference: Option<String>,
+    /// `/30/`: UID of the creditor as VAT number
+    pub uid: Option<Uid>,
+    /// `/31/`: Date, or period, of the service for VAT purposes
+    pub vat_date: Option<VatDate>,
+    /// `/32/`: VAT rate, or net amounts per rate
+    pub vat_details: Option<VatDetails>,
+    /// `/33/`: VAT paid on import, per rate
+    pub vat_import_tax: Option<VatImportTax>,
+    /// `/40/`: Payment conditions
+    pub conditions: Option<PaymentConditions>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VatDate {
+    Date(NaiveDate),
+    Period(NaiveDate, NaiveDate),
+}
+
+impl S1 {
+    /// The end of the net payment term, counted from the document date
+    pub fn due_date(&self) -> Option<NaiveDate> {
+        let days = self.conditions.as_ref()?.net_days()?;
+        Some(self.document_date? + Duration::days(days as i64))
+    }
+
+    /// Encode `due_date` as net payment term (`/40/0:days`), counted from
+    /// the document date, unless the payment conditions already end on it.
+    pub fn with_due_date(mut self, due_date: NaiveDate) -> Result<Self, Error> {
+        let document_date = self.document_date.ok_or(Error::MissingDocumentDate)?;
+        let days = u32::try_from((due_date - document_date).num_days())
+            .map_err(|_| Error::DueDateBeforeDocumentDate(due_date))?;
+        match self.due_date() {
+            Some(found) if found != due_date => Err(Error::DueDateMismatch { expected: due_date, found }),
+            Some(_) => Ok(self),
+            None => {
+                self.conditions
+                    .get_or_insert_with(|| PaymentConditions(vec![]))
+                    .0
+                    .push(PaymentCondition { discount: 0.0, days });
+                Ok(self)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for S1 {
+    type Err = Error;
+
+    /// Parse S1 bill information. Tags this crate does not know are ignored,
+    /// as asked for by the syntax definition.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let rest = text.trim().strip_prefix(PREFIX).ok_or(Error::InvalidPrefix)?;
+        let mut s1 = S1::default();
+        if rest.is_empty() {
+            return Ok(s1);
+        }
+        let rest = rest.strip_prefix('/').ok_or(Error::InvalidPrefix)?;
+        let mut parts = split_unescaped(rest).into_iter();
+        while let Some(tag) = parts.next() {
+            let value = parts.next().filter(|v| !v.is_empty()).ok_or_else(|| Error::MissingValue(tag.clone()))?;
+            fn set<T>(field: &mut Option<T>, tag: &str, value: T) -> Result<(), Error> {
+                match field.replace(value) {
+                    Some(_) => Err(Error::DuplicateTag(tag.to_string())),
+                    None => Ok(()),
+                }
+            }
+            match tag.as_str() {
+                "10" => set(&mut s1.invoice_number, &tag, value)?,
+                "11" => set(&mut s1.document_date, &tag, parse_date(&value)?)?,
+                "20" => set(&mut s1.customer_reference, &tag, value)?,
+                "30" => set(&mut s1.uid, &tag, format!("CHE{value}").parse().map_err(Error::InvalidUid)?)?,
+                "31" => set(&mut s1.vat_date, &tag, value.parse()?)?,
+                "32" => set(&mut s1.vat_details, &tag, value.parse()?)?,
+                "33" => set(&mut s1.vat_import_tax, &tag, value.parse()?)?,
+                "40" => set(&mut s1.conditions, &tag, value.parse()?)?,
+                _ => {}
+            }
+        }
+        Ok(s1)
+    }
+}
+
+impl std::fmt::Display for S1 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{PREFIX}")?;
+        let parts = [
+            ("10", self.invoice_number.as_deref().map(escape)),
+            ("11", self.document_date.map(|d| d.format(DATE_FORMAT).to_string())),
+            ("20", self.customer_reference.as_deref().map(escape)),
+            ("30", self.uid.map(|uid| uid.compact())),
+            ("31", self.vat_date.map(|d| d.to_string())),
+            ("32", self.vat_details.as_ref().map(|v| v.to_string())),
+            ("33", self.vat_import_tax.as_ref().map(|v| v.to_string())),
+            ("40", self.conditions.as_ref().map(|c| c.to_string())),
+        ];
+        for (tag, value) in parts {
+            if let Some(value) = value {
+                write!(f, "/{tag}/{value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for VatDate {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.len() {
+            12 if text.is_ascii() => Ok(VatDate::Period(parse_date(&text[..6])?, parse_date(&text[6..])?)),
+            _ => Ok(VatDate::Date(parse_date(text)?)),
+        }
+    }
+}
+
+impl std::fmt::Display for VatDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VatDate::Date(date) => write!(f, "{}", date.format(DATE_FORMAT)),
+            VatDate::Period(from, to) => write!(f, "{}{}", from.format(DATE_FORMAT), to.format(DATE_FORMAT)),
+        }
+    }
+}
+
+/// Payment conditions such as `2:10;0:30`: 2 % discount if paid within 10
+/// days of the document date, otherwise the full amount within 30 days.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentConditions(pub Vec<PaymentCondition>);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaymentCondition {
+    /// Discount in percent; 0 for the net payment term
+    pub discount: f64,
+    /// Days after the document date within which the discount applies
+    pub days: u32,
+}
+
+/// What is owed when paying on a given date
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settlement {
+    /// The discount applied, in percent
+    pub discount_percent: f64,
+    /// The discount applied, rounded to the centime
+    pub discount: f64,
+    /// The amount to pay after the discount
+    pub amount_due: f64,
+    /// The last day the discount applies, or the net payment term if there
+    /// is no discount
+    pub pay_by: Option<NaiveDate>,
+    /// Whether the payment date is after the net payment term
+    pub overdue: bool,
+}
+
+impl PaymentConditions {
+    /// The net payment term, given as condition with 0 % discount
+    pub fn net_days(&self) -> Option<u32> {
+        self.0.iter().filter(|c| c.discount == 0.0).map(|c| c.days).max()
+    }
+
+    /// Evaluate the conditions for a bill of `amount`, issued on
+    /// `document_date` and paid on `payment_date`.
+    ///
+    /// Of the conditions whose term has not passed on `payment_date`, the one
+    /// with the highest discount applies.
+    pub fn evaluate(&self, amount: f64, document_date: NaiveDate, payment_date: NaiveDate) -> Settlement {
+        let elapsed = (payment_date - document_date).num_days().max(0);
+        let applicable = self.0
+            .iter()
+            .filter(|c| c.days as i64 >= elapsed)
+            .max_by(|a, b| a.discount.total_cmp(&b.discount).then(b.days.cmp(&a.days)));
+        let discount_percent = applicable.map_or(0.0, |c| c.discount);
+        let discount = round_to_centime(amount * discount_percent / 100.0);
+        let net_days = self.net_days();
+        Settlement {
+            discount_percent,
+            discount,
+            amount_due: round_to_centime(amount - discount),
+            pay_by: applicable
+                .map(|c| c.days)
+                .or(net_days)
+                .map(|days| document_date + Duration::days(days as i64)),
+            overdue: net_days.is_some_and(|days| elapsed > days as i64),
+        }
+    }
+}
+
+impl std::str::FromStr for PaymentConditions {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        text.split(';')
+            .map(|condition| {
+                let invalid = || Error::InvalidCondition(condition.to_string());
+                let (discount, days) = condition.split_once(':').ok_or_else(invalid)?;
+                let discount: f64 = discount.parse().map_err(|_| invalid())?;
+                if !discount.is_finite() || !(0.0..100.0).contains(&discount) {
+                    return Err(invalid());
+                }
+                Ok(PaymentCondition { discount, days: days.parse().map_err(|_| invalid())? })
+            })
+            .collect::<Result<_, _>>()
+            .map(PaymentConditions)
+    }
+}
+
+impl std::fmt::Display for PaymentConditions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let conditions: Vec<_> = self.0.iter().map(|c| format!("{}:{}", c.discount, c.days)).collect();
+        write!(f, "{}", conditions.join(";"))
+    }
+}
+
+/// The VAT of a bill (`/32/`): either a single rate for the whole amount, or
+/// the net amounts per rate, e.g. `8:1000;2.5:51.8`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VatDetails {
+    Rate(f64),
+    Breakdown(Vec<VatAmount>),
+}
+
+/// VAT paid on import (`/33/`): the tax amounts per rate, e.g. `8:22.5`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VatImportTax(pub Vec<VatAmount>);
+
+/// An amount subject to a VAT rate: the net amount in `/32/`, the tax itself
+/// in `/33/`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VatAmount {
+    /// Rate in percent
+    pub rate: f64,
+    pub amount: f64,
+}
+
+impl VatDetails {
+    /// Sum up the net amounts of line items per rate, in the order the rates
+    /// first appear.
+    pub fn from_line_items(items: impl IntoIterator<Item = VatAmount>) -> Self {
+        let mut breakdown: Vec<VatAmount> = vec![];
+        for item in items {
+            match breakdown.iter_mut().find(|v| v.rate == item.rate) {
+                Some(v) => v.amount += item.amount,
+                None => breakdown.push(item),
+            }
+        }
+        for v in &mut breakdown {
+            v.amount = round_to_centime(v.amount);
+        }
+        VatDetails::Breakdown(breakdown)
+    }
+
+    /// The VAT per rate, each rounded to 5 centimes. `None` for a single rate
+    /// without amounts.
+    pub fn vat(&self) -> Option<Vec<VatAmount>> {
+        match self {
+            VatDetails::Rate(_) => None,
+            VatDetails::Breakdown(breakdown) => Some(breakdown
+                .iter()
+                .map(|v| VatAmount { rate: v.rate, amount: round_to_5_centimes(v.amount * v.rate / 100.0) })
+                .collect()),
+        }
+    }
+
+    /// The total VAT, rounded to 5 centimes per rate
+    pub fn vat_total(&self) -> Option<f64> {
+        self.vat().map(|vat| round_to_centime(vat.iter().map(|v| v.amount).sum()))
+    }
+
+    /// The net amounts plus their VAT
+    pub fn gross_total(&self) -> Option<f64> {
+        match self {
+            VatDetails::Rate(_) => None,
+            VatDetails::Breakdown(breakdown) => {
+                let net: f64 = breakdown.iter().map(|v| v.amount).sum();
+                Some(round_to_centime(net + self.vat_total()?))
+            }
+        }
+    }
+
+    /// Check that the net amounts plus VAT match the `amount` of the bill,
+    /// when both are known.
+    pub fn validate(&self, amount: Option<f64>) -> Result<(), Error> {
+        match (self.gross_total(), amount) {
+            (Some(expected), Some(found)) if (expected - found).abs() >= 0.005 =>
+                Err(Error::AmountMismatch { expected, found }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::str::FromStr for VatDetails {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text.contains(':') {
+            return parse_vat_amounts(text).map(VatDetails::Breakdown);
+        }
+        match text.parse() {
+            Ok(rate) if is_rate(rate) => Ok(VatDetails::Rate(rate)),
+            _ => Err(Error::InvalidVatAmount(text.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for VatDetails {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VatDetails::Rate(rate) => write!(f, "{rate}"),
+            VatDetails::Breakdown(breakdown) => write!(f, "{}", format_vat_amounts(breakdown)),
+        }
+    }
+}
+
+impl std::str::FromStr for VatImportTax {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse_vat_amounts(text).map(VatImportTax)
+    }
+}
+
+impl std::fmt::Display for VatImportTax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_vat_amounts(&self.0))
+    }
+}
+
+fn is_rate(rate: f64) -> bool {
+    rate.is_finite() && (0.0..100.0).contains(&rate)
+}
+
+fn parse_vat_amounts(text: &str) -> Result<Vec<VatAmount>, Error> {
+    text.split(';')
+        .map(|pair| {
+            let invalid = || Error::InvalidVatAmount(pair.to_string());
+            let (rate, amount) = pair.split_once(':').ok_or_else(invalid)?;
+            let rate: f64 = rate.parse().map_err(|_| invalid())?;
+            let amount: f64 = amount.parse().map_err(|_| invalid())?;
+            if !is_rate(rate) || !amount.is_finite() || amount < 0.0 {
+                return Err(invalid());
+            }
+            Ok(VatAmount { rate, amount })
+        })
+        .collect()
+}
+
+fn format_vat_amounts(amounts: &[VatAmount]) -> String {
+    let amounts: Vec<_> = amounts.iter().map(|v| format!("{}:{}", v.rate, v.amount)).collect();
+    amounts.join(";")
+}
+
+fn parse_date(text: &str) -> Result<NaiveDate, Error> {
+    let invalid = || Error::InvalidDate(text.to_string());
+    if text.len() != 6 || !text.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    NaiveDate::parse_from_str(text, DATE_FORMAT).map_err(|_| invalid())
+}
+
+pub(crate) fn round_to_centime(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// Round to the 5 centimes, the smallest coin in Swiss francs
+pub(crate) fn round_to_5_centimes(amount: f64) -> f64 {
+    (amount * 20.0).round() / 20.0
+}
+
+/// Escape the characters with a meaning in S1 in a value
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('/', "\\/")
+}
+
+/// Split at `/` not preceded by a `\`, and unescape the parts
+fn split_unescaped(text: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => parts.last_mut().unwrap().extend(chars.next()),
+            '/' => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = r"//S1/10/10201409/11/190512/20/1400.000-53\/A/30/106017086/31/180508180531/32/7.7/40/2:10;0:30";
+        let s1: S1 = text.parse().unwrap();
+        assert_eq!(s1, S1 {
+            invoice_number: Some("10201409".into()),
+            document_date: Some(date(2019, 5, 12)),
+            customer_reference: Some("1400.000-53/A".into()),
+            uid: Some("CHE-106.017.086".parse().unwrap()),
+            vat_date: Some(VatDate::Period(date(2018, 5, 8), date(2018, 5, 31))),
+            vat_details: Some(VatDetails::Rate(7.7)),
+            vat_import_tax: None,
+            conditions: Some(PaymentConditions(vec![
+                PaymentCondition { discount: 2.0, days: 10 },
+                PaymentCondition { discount: 0.0, days: 30 },
+            ])),
+        });
+        assert_eq!(s1.to_string(), text);
+    }
+
+    #[rstest]
+    #[case("//S1"                , Ok(S1::default()))]
+    #[case("//S1/99/ignored/10/1", Ok(S1 { invoice_number: Some("1".into()), ..S1::default() }))]
+    #[case("//S2/10/1"           , Err(Error::InvalidPrefix))]
+    #[case("//S1/10"             , Err(Error::MissingValue("10".into())))]
+    #[case("//S1/10/1/10/2"      , Err(Error::DuplicateTag("10".into())))]
+    #[case("//S1/11/191332"      , Err(Error::InvalidDate("191332".into())))]
+    #[case("//S1/40/2:10;0"      , Err(Error::InvalidCondition("0".into())))]
+    #[case("//S1/40/100:10"      , Err(Error::InvalidCondition("100:10".into())))]
+    fn parse(#[case] text: &str, #[case] expected: Result<S1, Error>) {
+        assert_eq!(text.parse::<S1>(), expected);
+    }
+
+    #[rstest]
+    //     paid on          , discount, amount due, pay by          , overdue
+    #[case(date(2024, 3,  1), 2.0     , 1910.76   , date(2024, 3, 11), false)]
+    #[case(date(2024, 3, 11), 2.0     , 1910.76   , date(2024, 3, 11), false)]
+    #[case(date(2024, 3, 12), 0.0     , 1949.75   , date(2024, 3, 31), false)]
+    #[case(date(2024, 3, 31), 0.0     , 1949.75   , date(2024, 3, 31), false)]
+    #[case(date(2024, 4,  1), 0.0     , 1949.75   , date(2024, 3, 31), true )]
+    #[case(date(2024, 2, 20), 2.0     , 1910.76   , date(2024, 3, 11), false)]
+    fn evaluate(
+        #[case] paid_on: NaiveDate,
+        #[case] discount_percent: f64,
+        #[case] amount_due: f64,
+        #[case] pay_by: NaiveDate,
+        #[case] overdue: bool,
+    ) {
+        let conditions: PaymentConditions = "0:30;2:10".parse().unwrap();
+        let settlement = conditions.evaluate(1949.75, date(2024, 3, 1), paid_on);
+        assert_eq!(settlement, Settlement {
+            discount_percent,
+            discount: round_to_centime(1949.75 - amount_due),
+            amount_due,
+            pay_by: Some(pay_by),
+            overdue,
+        });
+    }
+
+    #[test]
+    fn evaluate_without_net_term() {
+        let conditions: PaymentConditions = "3:5;1.5:20".parse().unwrap();
+        let settlement = conditions.evaluate(100.0, date(2024, 1, 1), date(2024, 1, 15));
+        assert_eq!((settlement.discount, settlement.amount_due), (1.5, 98.5));
+        let settlement = conditions.evaluate(100.0, date(2024, 1, 1), date(2024, 2, 15));
+        assert_eq!(settlement, Settlement {
+            discount_percent: 0.0,
+            discount: 0.0,
+            amount_due: 100.0,
+            pay_by: None,
+            overdue: false,
+        });
+    }
+
+    #[rstest]
+    #[case("//S1/11/240301"          , date(2024, 3, 31), Ok("//S1/11/240301/40/0:30"))]
+    #[case("//S1/11/240301/40/2:10"  , date(2024, 3, 31), Ok("//S1/11/240301/40/2:10;0:30"))]
+    #[case("//S1/11/240301/40/0:30"  , date(2024, 3, 31), Ok("//S1/11/240301/40/0:30"))]
+    #[case("//S1/11/240301"          , date(2024, 3,  1), Ok("//S1/11/240301/40/0:0"))]
+    #[case("//S1/10/1"               , date(2024, 3, 31), Err(Error::MissingDocumentDate))]
+    #[case("//S1/11/240301"          , date(2024, 2, 29), Err(Error::DueDateBeforeDocumentDate(date(2024, 2, 29))))]
+    #[case("//S1/11/240301/40/0:20"  , date(2024, 3, 31), Err(Error::DueDateMismatch { expected: date(2024, 3, 31), found: date(2024, 3, 21) }))]
+    fn with_due_date(#[case] text: &str, #[case] due_date: NaiveDate, #[case] expected: Result<&str, Error>) {
+        let s1 = text.parse::<S1>().unwrap().with_due_date(due_date);
+        assert_eq!(s1.as_ref().map(ToString::to_string).map_err(Clone::clone), expected.map(str::to_string));
+        if let Ok(s1) = s1 {
+            assert_eq!(s1.due_date(), Some(due_date));
+        }
+    }
+
+    #[test]
+    fn vat_from_line_items() {
+        let vat = VatDetails::from_line_items([
+            VatAmount { rate: 8.1, amount: 600.0  },
+            VatAmount { rate: 2.6, amount: 51.8   },
+            VatAmount { rate: 8.1, amount: 400.0  },
+        ]);
+        assert_eq!(vat.to_string(), "8.1:1000;2.6:51.8");
+        assert_eq!(vat.vat(), Some(vec![
+            VatAmount { rate: 8.1, amount: 81.0 },
+            VatAmount { rate: 2.6, amount: 1.35 }, // 1.3468
+        ]));
+        assert_eq!(vat.vat_total(), Some(82.35));
+        assert_eq!(vat.gross_total(), Some(1134.15));
+        assert_eq!(vat.validate(Some(1134.15)), Ok(()));
+        assert_eq!(vat.validate(None), Ok(()));
+        assert_eq!(vat.validate(Some(1134.1)), Err(Error::AmountMismatch { expected: 1134.15, found: 1134.1 }));
+    }
+
+    #[rstest]
+    #[case("8.1"              , Ok(VatDetails::Rate(8.1)))]
+    #[case("8:1000;2.5:51.8"  , Ok(VatDetails::Breakdown(vec![
+        VatAmount { rate: 8.0, amount: 1000.0 },
+        VatAmount { rate: 2.5, amount: 51.8   },
+    ])))]
+    #[case("8:"               , Err(Error::InvalidVatAmount("8:".into())))]
+    #[case("8:100;x:1"        , Err(Error::InvalidVatAmount("x:1".into())))]
+    #[case("8:-1"             , Err(Error::InvalidVatAmount("8:-1".into())))]
+    #[case("100"              , Err(Error::InvalidVatAmount("100".into())))]
+    fn parse_vat_details(#[case] text: &str, #[case] expected: Result<VatDetails, Error>) {
+        assert_eq!(text.parse::<VatDetails>(), expected);
+        if let Ok(vat) = expected {
+            assert_eq!(vat.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn parse_vat_import_tax() {
+        let s1: S1 = "//S1/10/1/33/7.7:48.37;2.5:12.4".parse().unwrap();
+        assert_eq!(s1.vat_import_tax, Some(VatImportTax(vec![
+            VatAmount { rate: 7.7, amount: 48.37 },
+            VatAmount { rate: 2.5, amount: 12.4  },
+        ])));
+        assert_eq!(s1.to_string(), "//S1/10/1/33/7.7:48.37;2.5:12.4");
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn parse_never_panics(input in "\\PC*") {
+            let _ = format!("//S1/{input}").parse::<S1>();
+        }
+    }
+}
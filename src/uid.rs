@@ -0,0 +1,151 @@
+//! Swiss business identification numbers (UID), which also serve as VAT
+//! numbers: `CHE-123.456.789`, with the suffix `MWST`, `TVA` or `IVA` when
+//! printed as a VAT number.
+
+use crate::checkdigit;
+
+/// A validated Swiss business identification number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Uid {
+    /// The 9 digits, the last of which is the check digit
+    digits: [u8; 9],
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("A UID must start with 'CHE'.")]
+    InvalidPrefix,
+    #[error("A UID must have 9 digits, but {0} were given.")]
+    InvalidLength(usize),
+    #[error("A UID may only contain digits after 'CHE', but '{0}' was given.")]
+    InvalidCharacter(char),
+    #[error("The suffix of a VAT number must be MWST, TVA or IVA, but '{0}' was given.")]
+    InvalidSuffix(String),
+    #[error("The check digit should be {expected}, but is {found}.")]
+    InvalidChecksum { expected: u8, found: u8 },
+    #[error("No UID is issued with the digits {0}: their check digit would be 10.")]
+    NotIssued(String),
+}
+
+/// Suffixes marking a UID as VAT number, in German, French and Italian
+const VAT_SUFFIXES: [&str; 3] = ["MWST", "TVA", "IVA"];
+
+impl Uid {
+    /// The compact form used inside bill information (Swico `/30/`):
+    /// the 9 digits without prefix, e.g. `123456789`.
+    pub fn compact(&self) -> String {
+        self.digits.iter().map(|d| char::from(b'0' + d)).collect()
+    }
+
+    /// The human form with the VAT suffix in the given language, e.g.
+    /// `CHE-123.456.789 MWST`.
+    pub fn vat_number(&self, language: crate::Language) -> String {
+        use crate::Language::*;
+        let suffix = match language {
+            German | English => "MWST",
+            French           => "TVA",
+            Italian          => "IVA",
+        };
+        format!("{self} {suffix}")
+    }
+}
+
+impl std::str::FromStr for Uid {
+    type Err = Error;
+
+    /// Parse `CHE-123.456.789`, `CHE123456789` or `CHE 123 456 789`, optionally
+    /// followed by `MWST`, `TVA` or `IVA`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let rest = text
+            .get(..3)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("CHE"))
+            .map(|_| &text[3..])
+            .ok_or(Error::InvalidPrefix)?;
+
+        // Split off the VAT suffix, after the last digit
+        let end = rest.rfind(|c: char| c.is_ascii_digit()).map_or(0, |i| i + 1);
+        let (number, suffix) = rest.split_at(end);
+        let suffix = suffix.trim();
+        if !suffix.is_empty() && !VAT_SUFFIXES.iter().any(|s| s.eq_ignore_ascii_case(suffix)) {
+            return Err(Error::InvalidSuffix(suffix.to_string()));
+        }
+
+        let number: String = number
+            .chars()
+            .filter(|c| !matches!(c, '-' | '.' | ' '))
+            .collect();
+        if let Some(c) = number.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(Error::InvalidCharacter(c));
+        }
+        if number.len() != 9 {
+            return Err(Error::InvalidLength(number.len()));
+        }
+
+        let mut digits = [0; 9];
+        for (digit, b) in digits.iter_mut().zip(number.bytes()) {
+            *digit = b - b'0';
+        }
+        let expected = checkdigit::uid_mod11(&number[..8])
+            .ok_or_else(|| Error::NotIssued(number[..8].to_string()))?;
+        let found = digits[8];
+        if expected != found {
+            return Err(Error::InvalidChecksum { expected, found });
+        }
+        Ok(Self { digits })
+    }
+}
+
+impl std::fmt::Display for Uid {
+    /// The human form without VAT suffix, e.g. `CHE-123.456.789`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.compact();
+        write!(f, "CHE-{}.{}.{}", &digits[..3], &digits[3..6], &digits[6..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Language;
+    use rstest::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case("CHE-109.322.551"     )]
+    #[case("CHE109322551"        )]
+    #[case("che-109.322.551"     )]
+    #[case(" CHE 109 322 551 "   )]
+    #[case("CHE-109.322.551 MWST")]
+    #[case("CHE109322551MWST"    )]
+    #[case("CHE-109.322.551 TVA" )]
+    #[case("CHE-109.322.551 iva" )]
+    fn parse(#[case] text: &str) {
+        let uid: Uid = text.parse().unwrap();
+        assert_eq!(uid.to_string(), "CHE-109.322.551");
+        assert_eq!(uid.compact(), "109322551");
+        assert_eq!(uid.vat_number(Language::German ), "CHE-109.322.551 MWST");
+        assert_eq!(uid.vat_number(Language::French ), "CHE-109.322.551 TVA");
+        assert_eq!(uid.vat_number(Language::Italian), "CHE-109.322.551 IVA");
+    }
+
+    #[rstest]
+    #[case(""                    , Error::InvalidPrefix)]
+    #[case("DE-109.322.551"      , Error::InvalidPrefix)]
+    #[case("CHE-109.322.55"      , Error::InvalidLength(8))]
+    #[case("CHE-109.322.5511"    , Error::InvalidLength(10))]
+    #[case("CHE-109/322/551"     , Error::InvalidCharacter('/'))]
+    #[case("CHE-109.322.551 VAT" , Error::InvalidSuffix("VAT".into()))]
+    #[case("CHE-109.322.552"     , Error::InvalidChecksum { expected: 1, found: 2 })]
+    #[case("CHE-000.000.030"     , Error::NotIssued("00000003".into()))]
+    fn parse_rejects(#[case] text: &str, #[case] expected: Error) {
+        assert_eq!(text.parse::<Uid>().unwrap_err(), expected);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn parse_never_panics(input in "\\PC*") {
+            let _ = input.parse::<Uid>();
+        }
+    }
+}